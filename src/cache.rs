@@ -1,186 +1,418 @@
-use std::{collections::HashMap, path::Path};
+//! Local copies of the price API's datasets.
+//!
+//! Everything is stored under a single cache directory, which is taken from
+//! `--cache-dir`, `$OSRS_MM_CACHE_DIR`, `$XDG_CACHE_HOME/osrs-mm` or
+//! `~/.cache/osrs-mm` in that order. Each game mode has its own subdirectory,
+//! so prices of one economy are never mixed into another. Files are written to
+//! a temporary file and renamed into place, so an interrupted run never leaves
+//! a truncated file.
 
-use eyre::Result;
-use serde::de::DeserializeOwned;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::item::{DailyVolumeResponse, Item, ItemPrice, ItemPriceResponse, ItemWithPrice};
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
 
-const ITEM_MAP_CACHE_FILEPATH: &str = "cache/item_map.json";
-const PRICES_CACHE_FILEPATH: &str = "cache/prices.json";
-const VOLUMES_CACHE_FILEPATH: &str = "cache/volumes.json";
+use crate::{
+    dataset::{self, Dataset, Metadata, Stored},
+    http::Client,
+    item::{
+        AveragePriceResponse, DailyVolumeResponse, Item, ItemError, ItemPrice, ItemPriceResponse,
+        ItemWithPrice,
+    },
+};
 
-type ItemMap = HashMap<usize, Item>;
-type ItemPriceMap = HashMap<usize, ItemPrice>;
-type VolumeMap = HashMap<usize, usize>;
+/// Where the price API is served from, unless configured otherwise. The
+/// endpoints of each game mode are below it, e.g. `<root>/osrs/latest`.
+pub const DEFAULT_API_ROOT: &str = "https://prices.runescape.wiki/api/v1";
 
-pub struct Cache {
-    pub items: ItemMap,
-    pub prices: ItemPriceMap,
-    pub volumes: VolumeMap,
+pub type ItemMap = HashMap<usize, Item>;
+pub type ItemPriceMap = HashMap<usize, ItemPrice>;
+pub type VolumeMap = HashMap<usize, usize>;
+
+/// Where price data is taken from
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum PriceSource {
+    /// Most recent instant-buy and instant-sell prices
+    #[default]
+    #[serde(rename = "latest")]
+    Latest,
+    /// Average prices over the last 5 minutes
+    #[value(name = "5m")]
+    #[serde(rename = "5m")]
+    FiveMinute,
+    /// Average prices over the last hour
+    #[value(name = "1h")]
+    #[serde(rename = "1h")]
+    OneHour,
 }
 
-impl Cache {
-    pub fn new(force: bool) -> Result<Self> {
-        Ok(Self {
-            items: load_item_map()?,
-            prices: load_price_map(force)?,
-            volumes: load_volume_map(force)?,
-        })
+impl PriceSource {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            PriceSource::Latest => "latest",
+            PriceSource::FiveMinute => "5m",
+            PriceSource::OneHour => "1h",
+        }
     }
 
-    pub fn get(&self, id: usize) -> ItemWithPrice {
-        let item = self
-            .items
-            .get(&id)
-            .expect("I hardcode all ids and know them in advance");
+    /// Length of the window prices are averaged over, in seconds. `None` for
+    /// the latest prices.
+    pub fn window(&self) -> Option<u64> {
+        match self {
+            PriceSource::Latest => None,
+            PriceSource::FiveMinute => Some(5 * 60),
+            PriceSource::OneHour => Some(60 * 60),
+        }
+    }
 
-        let price = self.prices.get(&id);
-        let volume = self.volumes.get(&id).map(|f| *f).unwrap_or_default();
-        ItemWithPrice {
-            item,
-            price,
-            volume,
+    fn cache_filename(&self) -> &'static str {
+        match self {
+            PriceSource::Latest => "prices.json",
+            PriceSource::FiveMinute => "prices_5m.json",
+            PriceSource::OneHour => "prices_1h.json",
         }
     }
 }
 
-fn get<T: DeserializeOwned>(url: &str) -> eyre::Result<T> {
-    let agent = ureq::AgentBuilder::new()
-        .user_agent("osrs-mm/v0.0.1")
-        .build();
+/// Which economy prices are taken from
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GameMode {
+    /// The main game
+    #[default]
+    Osrs,
+    /// Deadman mode
+    Dmm,
+    /// Fresh Start Worlds
+    Fsw,
+}
 
-    let response = agent.get(url).call()?;
-    let body = response.into_string()?;
-    let json = serde_json::from_str(&body)?;
-    Ok(json)
+impl GameMode {
+    /// Path of the mode's endpoints under the API root, also used to name its
+    /// cache directory
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameMode::Osrs => "osrs",
+            GameMode::Dmm => "dmm",
+            GameMode::Fsw => "fsw",
+        }
+    }
 }
 
-fn item_map_from_url() -> eyre::Result<ItemMap> {
-    Ok(
-        get::<Vec<Item>>("https://prices.runescape.wiki/api/v1/osrs/mapping")?
-            .into_iter()
-            .map(|i| (i.id, i))
-            .collect(),
-    )
+/// How long each cached dataset can be reused before it is fetched again
+#[derive(Copy, Clone, Debug)]
+pub struct MaxAge {
+    pub mapping: Duration,
+    pub prices: Duration,
+    pub volumes: Duration,
 }
 
-fn item_from_cache() -> Option<ItemMap> {
-    let path = Path::new(ITEM_MAP_CACHE_FILEPATH);
-    if !path.exists() {
-        return None;
+impl Default for MaxAge {
+    fn default() -> Self {
+        Self {
+            mapping: Duration::from_secs(24 * 60 * 60),
+            prices: Duration::from_secs(60),
+            volumes: Duration::from_secs(60 * 60),
+        }
     }
+}
 
-    let contents = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&contents).ok()
+/// The `/mapping` endpoint: static data of every item
+pub fn mapping(base_url: &str, max_age: Duration) -> Dataset<ItemMap> {
+    Dataset::new(
+        "mapping",
+        format!("{base_url}/mapping"),
+        "item_map.json",
+        max_age,
+        parse_mapping,
+    )
 }
 
-fn write_to_cache(items: &ItemMap) -> Result<()> {
-    let contents = serde_json::to_string_pretty(&items)?;
-    let path = Path::new(ITEM_MAP_CACHE_FILEPATH);
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent)?;
+/// The `/latest`, `/5m` or `/1h` endpoint depending on `source`
+pub fn prices(base_url: &str, source: PriceSource, max_age: Duration) -> Dataset<ItemPriceMap> {
+    let parse = match source {
+        PriceSource::Latest => parse_latest,
+        PriceSource::FiveMinute => |body: &str| parse_average(body, 5 * 60),
+        PriceSource::OneHour => |body: &str| parse_average(body, 60 * 60),
+    };
+    Dataset::new(
+        source.endpoint(),
+        format!("{base_url}/{}", source.endpoint()),
+        source.cache_filename(),
+        max_age,
+        parse,
+    )
+}
+
+/// The `/volumes` endpoint: daily volume of every item
+pub fn volumes(base_url: &str, max_age: Duration) -> Dataset<VolumeMap> {
+    Dataset::new(
+        "volumes",
+        format!("{base_url}/volumes"),
+        "volumes.json",
+        max_age,
+        |body| Ok(serde_json::from_str::<DailyVolumeResponse>(body)?.data),
+    )
+}
+
+/// Every dataset that can be stored in the cache
+pub fn datasets(base_url: &str, max_age: &MaxAge) -> Vec<Box<dyn Stored>> {
+    vec![
+        Box::new(mapping(base_url, max_age.mapping)),
+        Box::new(prices(base_url, PriceSource::Latest, max_age.prices)),
+        Box::new(prices(base_url, PriceSource::FiveMinute, max_age.prices)),
+        Box::new(prices(base_url, PriceSource::OneHour, max_age.prices)),
+        Box::new(volumes(base_url, max_age.volumes)),
+    ]
+}
+
+/// The datasets called `names`, or every dataset if there are none
+pub fn select(base_url: &str, max_age: &MaxAge, names: &[String]) -> Result<Vec<Box<dyn Stored>>> {
+    let all = datasets(base_url, max_age);
+    for name in names {
+        if !all.iter().any(|d| d.name() == name) {
+            let known = all.iter().map(|d| d.name()).collect::<Vec<_>>();
+            bail!(
+                "unknown dataset {name:?}, expected one of {}",
+                known.join(", ")
+            );
         }
     }
-    std::fs::write(path, contents)?;
-    Ok(())
+
+    Ok(all
+        .into_iter()
+        .filter(|d| names.is_empty() || names.iter().any(|name| d.name() == name))
+        .collect())
 }
 
-fn load_item_map() -> Result<ItemMap> {
-    Ok(match item_from_cache() {
-        Some(items) => items,
-        None => {
-            let items = item_map_from_url()?;
-            write_to_cache(&items)?;
-            items
-        }
-    })
+/// What is stored of a dataset
+#[derive(Clone, Debug, Serialize)]
+pub struct Status {
+    pub name: &'static str,
+    pub path: PathBuf,
+    /// Number of items, if the dataset is stored and readable
+    pub items: Option<usize>,
+    /// Size of the stored file in bytes
+    pub size: Option<u64>,
+    #[serde(flatten)]
+    pub metadata: Option<Metadata>,
 }
 
-fn prices_from_url() -> Result<ItemPriceMap> {
-    let response: ItemPriceResponse = get("https://prices.runescape.wiki/api/v1/osrs/latest")?;
-    Ok(response.data)
+pub fn status(dir: &Path, datasets: &[Box<dyn Stored>]) -> Vec<Status> {
+    datasets
+        .iter()
+        .map(|d| Status {
+            name: d.name(),
+            path: d.path(dir),
+            items: d.count(dir).ok(),
+            size: std::fs::metadata(d.path(dir)).ok().map(|meta| meta.len()),
+            metadata: d.metadata(dir),
+        })
+        .collect()
 }
 
-fn prices_from_cache() -> Option<ItemPriceMap> {
-    let path = Path::new(PRICES_CACHE_FILEPATH);
-    if !path.exists() {
-        return None;
-    }
+/// Datasets exported from a cache, to be imported into another one
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    schema_version: usize,
+    datasets: Vec<SnapshotEntry>,
+}
 
-    let contents = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&contents).ok()
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotEntry {
+    name: String,
+    metadata: Metadata,
+    data: serde_json::Value,
 }
 
-fn write_prices_to_cache(prices: &ItemPriceMap) -> Result<()> {
-    let contents = serde_json::to_string_pretty(&prices)?;
-    let path = Path::new(PRICES_CACHE_FILEPATH);
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent)?;
-        }
+/// Write every stored dataset of `datasets` to a single JSON file at `path`.
+/// Returns the names of the exported datasets.
+pub fn export(dir: &Path, datasets: &[Box<dyn Stored>], path: &Path) -> Result<Vec<&'static str>> {
+    let mut snapshot = Snapshot {
+        schema_version: dataset::SCHEMA_VERSION,
+        datasets: Vec::new(),
+    };
+    let mut exported = Vec::new();
+    for d in datasets {
+        let Ok(contents) = std::fs::read_to_string(d.path(dir)) else {
+            continue;
+        };
+        let metadata = d.metadata(dir).unwrap_or_else(|| Metadata {
+            fetched_at: std::fs::metadata(d.path(dir))
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|age| age.as_secs())
+                .unwrap_or_default(),
+            source_url: d.url().to_string(),
+            etag: None,
+            schema_version: dataset::SCHEMA_VERSION,
+        });
+        snapshot.datasets.push(SnapshotEntry {
+            name: d.name().to_string(),
+            metadata,
+            data: serde_json::from_str(&contents)
+                .wrap_err_with(|| format!("invalid {}", d.path(dir).display()))?,
+        });
+        exported.push(d.name());
     }
-    std::fs::write(path, contents)?;
-    Ok(())
+
+    write_atomic(path, serde_json::to_string(&snapshot)?.as_bytes())?;
+    Ok(exported)
 }
 
-fn load_price_map(force: bool) -> Result<ItemPriceMap> {
-    if force {
-        let prices = prices_from_url()?;
-        write_prices_to_cache(&prices)?;
-        Ok(prices)
-    } else {
-        Ok(match prices_from_cache() {
-            Some(prices) => prices,
-            None => {
-                let prices = prices_from_url()?;
-                write_prices_to_cache(&prices)?;
-                prices
-            }
-        })
+/// Store every dataset of the snapshot at `path` that is in `datasets`,
+/// keeping the time it was originally fetched at. Returns the names of the
+/// imported datasets.
+pub fn import(dir: &Path, datasets: &[Box<dyn Stored>], path: &Path) -> Result<Vec<&'static str>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let snapshot: Snapshot = serde_json::from_str(&contents)
+        .wrap_err_with(|| format!("invalid snapshot {}", path.display()))?;
+    if snapshot.schema_version != dataset::SCHEMA_VERSION {
+        bail!(
+            "snapshot {} has schema version {}, expected {}",
+            path.display(),
+            snapshot.schema_version,
+            dataset::SCHEMA_VERSION
+        );
     }
+
+    let mut imported = Vec::new();
+    for entry in &snapshot.datasets {
+        let Some(d) = datasets.iter().find(|d| d.name() == entry.name) else {
+            continue;
+        };
+        d.import(dir, &serde_json::to_string(&entry.data)?, &entry.metadata)?;
+        imported.push(d.name());
+    }
+    Ok(imported)
 }
 
-fn volumes_from_url() -> Result<VolumeMap> {
-    let response: DailyVolumeResponse = get("https://prices.runescape.wiki/api/v1/osrs/volumes")?;
-    Ok(response.data)
+/// Parse a response of the `/mapping` endpoint
+pub fn parse_mapping(body: &str) -> Result<ItemMap> {
+    Ok(serde_json::from_str::<Vec<Item>>(body)?
+        .into_iter()
+        .map(|i| (i.id, i))
+        .collect())
 }
 
-fn volumes_from_cache() -> Option<VolumeMap> {
-    let path = Path::new(VOLUMES_CACHE_FILEPATH);
-    if !path.exists() {
-        return None;
+fn parse_latest(body: &str) -> Result<ItemPriceMap> {
+    Ok(serde_json::from_str::<ItemPriceResponse>(body)?.data)
+}
+
+/// Parse a response of the `/5m` or `/1h` endpoint, averaged over `window`
+/// seconds. The response is timed at the start of its window, but its prices
+/// are as recent as the end of it.
+pub fn parse_average(body: &str, window: u64) -> Result<ItemPriceMap> {
+    let response: AveragePriceResponse = serde_json::from_str(body)?;
+    let end = response.timestamp + window as usize;
+    Ok(response
+        .data
+        .into_iter()
+        .map(|(id, price)| (id, price.into_item_price(end)))
+        .collect())
+}
+
+#[derive(Default)]
+pub struct Cache {
+    pub items: ItemMap,
+    pub prices: ItemPriceMap,
+    pub volumes: VolumeMap,
+}
+
+impl Cache {
+    pub fn new(
+        dir: &Path,
+        client: &dyn Client,
+        source: PriceSource,
+        max_age: &MaxAge,
+    ) -> Result<Self> {
+        Ok(Self {
+            items: mapping(client.base_url(), max_age.mapping).load(dir, client)?,
+            prices: prices(client.base_url(), source, max_age.prices).load(dir, client)?,
+            volumes: volumes(client.base_url(), max_age.volumes).load(dir, client)?,
+        })
     }
 
-    let contents = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&contents).ok()
+    pub fn get(&self, id: usize) -> Result<ItemWithPrice<'_>, ItemError> {
+        let item = self.items.get(&id).ok_or(ItemError::UnknownItem { id })?;
+
+        let price = self.prices.get(&id);
+        let volume = self.volumes.get(&id).copied().unwrap_or_default();
+        Ok(ItemWithPrice {
+            item,
+            price,
+            volume,
+        })
+    }
 }
 
-fn write_volumes_to_cache(volumes: &VolumeMap) -> Result<()> {
-    let contents = serde_json::to_string_pretty(&volumes)?;
-    let path = Path::new(VOLUMES_CACHE_FILEPATH);
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent)?;
-        }
+#[cfg(test)]
+impl Cache {
+    /// Add an item with a buy `limit`, last traded at `low` and `high` at time
+    /// 0, with `volume` traded a day
+    pub fn with_item(
+        mut self,
+        id: usize,
+        name: &str,
+        limit: usize,
+        (low, high): (usize, usize),
+        volume: usize,
+    ) -> Self {
+        self.items.insert(
+            id,
+            Item {
+                id,
+                name: name.to_string(),
+                examine: String::new(),
+                members: true,
+                low_alch: None,
+                high_alch: None,
+                limit: Some(limit),
+                value: 0,
+            },
+        );
+        self.prices.insert(
+            id,
+            ItemPrice {
+                high: Some(high),
+                high_time: Some(0),
+                low: Some(low),
+                low_time: Some(0),
+                high_volume: None,
+                low_volume: None,
+            },
+        );
+        self.volumes.insert(id, volume);
+        self
     }
-    std::fs::write(path, contents)?;
-    Ok(())
 }
 
-fn load_volume_map(force: bool) -> Result<VolumeMap> {
-    if force {
-        let volumes = volumes_from_url()?;
-        write_volumes_to_cache(&volumes)?;
-        Ok(volumes)
-    } else {
-        Ok(match volumes_from_cache() {
-            Some(volumes) => volumes,
-            None => {
-                let volumes = volumes_from_url()?;
-                write_volumes_to_cache(&volumes)?;
-                volumes
-            }
-        })
+/// `$XDG_CACHE_HOME/osrs-mm`, falling back to `~/.cache/osrs-mm`
+pub fn default_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(dir.join("osrs-mm"))
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into
+/// place, so readers see either the old or the new file in full
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("failed to create {}", parent.display()))?;
     }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents)
+        .wrap_err_with(|| format!("failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    Ok(())
 }
@@ -0,0 +1,94 @@
+//! Everything known about a single item, as shown by `osrs-mm item`.
+
+use serde::Serialize;
+
+use crate::{cache::Cache, item::ItemError, makers::recipes::name, recipe::RecipeBook, tax};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Card {
+    pub id: usize,
+    pub name: String,
+    pub examine: String,
+    pub members: bool,
+    pub limit: Option<usize>,
+    pub low_alch: Option<usize>,
+    pub high_alch: Option<usize>,
+    pub high: Option<usize>,
+    /// Unix timestamp of when the high price was last traded
+    pub high_time: Option<usize>,
+    pub low: Option<usize>,
+    /// Unix timestamp of when the low price was last traded
+    pub low_time: Option<usize>,
+    /// Difference between the high and low price
+    pub spread: Option<isize>,
+    /// GE tax paid selling one at the high price
+    pub tax: Option<usize>,
+    /// Profit of buying one at the low price and selling it at the high price
+    pub margin: Option<isize>,
+    pub volume: usize,
+    /// Names of the recipes using the item as an input or rune, and of the
+    /// potion it is a dose of
+    pub consumed_by: Vec<String>,
+    /// Names of the recipes making the item, and of the potion it is a dose of
+    pub produced_by: Vec<String>,
+}
+
+pub fn card(cache: &Cache, recipes: &RecipeBook, id: usize) -> Result<Card, ItemError> {
+    let item = cache.get(id)?;
+    let high = item.high().ok();
+    let low = item.low().ok();
+    let tax = high.map(|high| tax::per_item(id, high));
+
+    // Any dose can be decanted into any other, so it is both used and made
+    let decanting = recipes
+        .potions
+        .iter()
+        .filter(|p| p.ids().contains(&id))
+        .map(|p| format!("Decanting {}", p.name))
+        .collect::<Vec<_>>();
+    let consumed_by = recipes
+        .recipes
+        .iter()
+        .filter(|r| {
+            r.inputs.iter().any(|i| i.item.id() == Ok(id))
+                || r.runes.iter().any(|r| r.item.id() == Ok(id))
+        })
+        .map(|r| name(cache, r))
+        .chain(decanting.clone())
+        .collect();
+    let produced_by = recipes
+        .recipes
+        .iter()
+        .filter(|r| {
+            r.outputs.iter().any(|o| o.item.id() == Ok(id))
+                || r.decant.as_ref().is_some_and(|d| d.item.id() == Ok(id))
+        })
+        .map(|r| name(cache, r))
+        .chain(decanting)
+        .collect();
+
+    Ok(Card {
+        id,
+        name: item.item.name.clone(),
+        examine: item.item.examine.clone(),
+        members: item.item.members,
+        limit: item.item.limit,
+        low_alch: item.item.low_alch,
+        high_alch: item.item.high_alch,
+        high,
+        high_time: item.price.and_then(|p| p.high_time),
+        low,
+        low_time: item.price.and_then(|p| p.low_time),
+        spread: high
+            .zip(low)
+            .map(|(high, low)| high as isize - low as isize),
+        tax,
+        margin: high
+            .zip(low)
+            .zip(tax)
+            .map(|((high, low), tax)| high as isize - tax as isize - low as isize),
+        volume: item.volume,
+        consumed_by,
+        produced_by,
+    })
+}
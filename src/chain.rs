@@ -0,0 +1,303 @@
+//! Production chains made of several recipes done one after another.
+//!
+//! Recipes are linked when the first output of one is the first input of the
+//! next, in the same quantity, e.g. cleaning a grimy herb and then making an
+//! unfinished potion with it. Every stretch of a chain can be done on its own by
+//! buying the item it starts at and selling the one it ends at, so each is
+//! evaluated as a single recipe. Items used along the way that aren't part of
+//! the chain, such as secondaries, are bought at each step's price.
+
+use serde::Serialize;
+
+use crate::{
+    cache::Cache,
+    makers::{
+        recipes::{evaluate, item_name},
+        ReportEntry,
+    },
+    recipe::{Recipe, RecipeBook},
+};
+
+/// Part of a chain, from buying `buy` to selling `sell`
+#[derive(Clone, Debug, Serialize)]
+pub struct Segment {
+    pub buy: String,
+    pub sell: String,
+    #[serde(flatten)]
+    pub entry: ReportEntry,
+}
+
+/// The evaluated segments of a chain. A segment is missing when the price or
+/// limit of one of its items isn't known.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChainReport {
+    /// Names of the items the chain goes through, in order
+    pub items: Vec<String>,
+    /// Each step done on its own
+    pub stages: Vec<Option<Segment>>,
+    /// Most profitable segment ending at or before `items[i + 1]`, i.e. where
+    /// to buy and sell when going at most that far
+    pub best: Vec<Option<Segment>>,
+    /// Buying the first item and selling the last
+    pub whole: Option<Segment>,
+}
+
+/// Every chain of at least two recipes that can't be extended any further
+fn chains(book: &RecipeBook) -> Vec<Vec<&Recipe>> {
+    let links = |from: &Recipe| -> Vec<&Recipe> {
+        let output = &from.outputs[0];
+        book.recipes
+            .iter()
+            .filter(|to| {
+                to.inputs[0].item == output.item && to.inputs[0].quantity == output.quantity
+            })
+            .collect()
+    };
+    let is_linked = |recipe: &Recipe| {
+        book.recipes
+            .iter()
+            .any(|from| links(from).iter().any(|to| std::ptr::eq(*to, recipe)))
+    };
+
+    let mut chains = Vec::new();
+    let mut stack: Vec<Vec<&Recipe>> = book
+        .recipes
+        .iter()
+        .filter(|recipe| !is_linked(recipe))
+        .map(|recipe| vec![recipe])
+        .collect();
+    while let Some(chain) = stack.pop() {
+        let next: Vec<_> = links(chain[chain.len() - 1])
+            .into_iter()
+            .filter(|recipe| !chain.iter().any(|r| std::ptr::eq(*r, *recipe)))
+            .collect();
+        if next.is_empty() {
+            if chain.len() > 1 {
+                chains.push(chain);
+            }
+            continue;
+        }
+        for recipe in next {
+            let mut longer = chain.clone();
+            longer.push(recipe);
+            stack.push(longer);
+        }
+    }
+    chains.reverse();
+    chains
+}
+
+/// A single recipe doing every step of `steps` in turn
+fn compose(steps: &[&Recipe]) -> Recipe {
+    let (first, last) = (steps[0], steps[steps.len() - 1]);
+    let mut inputs = vec![first.inputs[0].clone()];
+    let mut outputs = vec![last.outputs[0].clone()];
+    let mut runes = Vec::new();
+    let mut fee = 0;
+    let mut hours_per_action = Some(0.0);
+    for step in steps {
+        inputs.extend(step.inputs[1..].iter().cloned());
+        if !std::ptr::eq(*step, last) {
+            outputs.extend(step.outputs[1..].iter().cloned());
+        }
+        runes.extend(step.runes.iter().cloned());
+        fee += step.fee;
+        hours_per_action = hours_per_action
+            .zip(step.actions_per_hour.filter(|aph| *aph > 0))
+            .map(|(hours, aph)| hours + 1.0 / aph as f64);
+    }
+    outputs.extend(last.outputs[1..].iter().cloned());
+
+    Recipe {
+        name: Some("{input} -> {output}".to_string()),
+        category: "chains".to_string(),
+        inputs,
+        outputs,
+        runes,
+        decant: last.decant.clone(),
+        fee,
+        limiting: None,
+        limit: first.limit,
+        actions_per_hour: hours_per_action.map(|hours| (1.0 / hours) as usize),
+        notes: None,
+    }
+}
+
+fn segment(cache: &Cache, book: &RecipeBook, steps: &[&Recipe]) -> Option<Segment> {
+    let entry = evaluate(cache, &compose(steps), &book.potions).ok()?;
+    Some(Segment {
+        buy: entry.inputs[0].name.clone(),
+        // The potion may be sold decanted rather than as made
+        sell: entry.outputs[0].name.clone(),
+        entry,
+    })
+}
+
+/// Evaluate every segment of `chain`
+fn evaluate_chain(cache: &Cache, book: &RecipeBook, chain: &[&Recipe]) -> ChainReport {
+    let items = std::iter::once(&chain[0].inputs[0].item)
+        .chain(chain.iter().map(|step| &step.outputs[0].item))
+        .map(|item| item_name(cache, item))
+        .collect();
+
+    let mut stages = Vec::new();
+    let mut best: Vec<Option<Segment>> = Vec::new();
+    for end in 1..=chain.len() {
+        stages.push(segment(cache, book, &chain[end - 1..end]));
+        let ending_here = (0..end).filter_map(|start| segment(cache, book, &chain[start..end]));
+        let previous = best.last().cloned().flatten();
+        best.push(
+            ending_here
+                .chain(previous)
+                .max_by_key(|segment| segment.entry.profit),
+        );
+    }
+
+    ChainReport {
+        items,
+        stages,
+        best,
+        whole: segment(cache, book, chain),
+    }
+}
+
+/// Evaluate every chain of the recipes, the most profitable first
+pub fn report(cache: &Cache, book: &RecipeBook) -> Vec<ChainReport> {
+    let mut reports: Vec<_> = chains(book)
+        .iter()
+        .map(|chain| evaluate_chain(cache, book, chain))
+        .collect();
+    let profit = |report: &ChainReport| {
+        report
+            .best
+            .last()
+            .cloned()
+            .flatten()
+            .map_or(isize::MIN, |segment| segment.entry.profit)
+    };
+    reports.sort_by_key(|report| std::cmp::Reverse(profit(report)));
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRIMY_RANARR: usize = 207;
+    const RANARR: usize = 257;
+    const VIAL_OF_WATER: usize = 227;
+    const RANARR_UNF: usize = 99;
+    const SNAPE_GRASS: usize = 231;
+    const PRAYER_POTION: usize = 139;
+    const SOFT_CLAY: usize = 1761;
+
+    /// Cleaning a grimy ranarr, making an unfinished potion with it and then a
+    /// prayer potion, along with an unrelated recipe
+    fn book() -> RecipeBook {
+        RecipeBook::from_toml(
+            r#"
+            [[recipe]]
+            category = "cleaning"
+            inputs = [{ item = 207 }]
+            outputs = [{ item = 257 }]
+            actions_per_hour = 5_000
+
+            [[recipe]]
+            category = "herbs"
+            inputs = [{ item = 257 }, { item = 227 }]
+            outputs = [{ item = 99 }]
+            actions_per_hour = 2_500
+
+            [[recipe]]
+            category = "potions"
+            inputs = [{ item = 99 }, { item = 231 }]
+            outputs = [{ item = 139 }]
+            actions_per_hour = 2_500
+
+            [[recipe]]
+            category = "clay"
+            inputs = [{ item = 434 }]
+            outputs = [{ item = 1761 }]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn cache() -> Cache {
+        [
+            (GRIMY_RANARR, "Grimy ranarr weed", (6_000, 6_100)),
+            (RANARR, "Ranarr weed", (6_500, 6_600)),
+            (VIAL_OF_WATER, "Vial of water", (4, 5)),
+            (RANARR_UNF, "Ranarr potion (unf)", (6_700, 7_000)),
+            (SNAPE_GRASS, "Snape grass", (100, 110)),
+            (PRAYER_POTION, "Prayer potion(3)", (10_000, 10_500)),
+            (SOFT_CLAY, "Soft clay", (200, 210)),
+        ]
+        .into_iter()
+        .fold(Cache::default(), |cache, (id, name, price)| {
+            cache.with_item(id, name, 1_000, price, 1_000_000)
+        })
+    }
+
+    #[test]
+    fn links_and_extends_chains() {
+        let book = book();
+        let chains = chains(&book);
+
+        assert_eq!(chains.len(), 1);
+        let categories: Vec<_> = chains[0].iter().map(|r| r.category.as_str()).collect();
+        assert_eq!(categories, ["cleaning", "herbs", "potions"]);
+    }
+
+    #[test]
+    fn composes_steps() {
+        let book = book();
+        let steps: Vec<_> = book.recipes[..3].iter().collect();
+        let recipe = compose(&steps);
+
+        let inputs: Vec<_> = recipe.inputs.iter().map(|i| i.item.id()).collect();
+        assert_eq!(
+            inputs,
+            [Ok(GRIMY_RANARR), Ok(VIAL_OF_WATER), Ok(SNAPE_GRASS)]
+        );
+        let outputs: Vec<_> = recipe.outputs.iter().map(|o| o.item.id()).collect();
+        assert_eq!(outputs, [Ok(PRAYER_POTION)]);
+        // 1,000 of each step take a fifth of an hour cleaning and two fifths for
+        // each potion
+        assert_eq!(recipe.actions_per_hour, Some(1_000));
+    }
+
+    #[test]
+    fn best_segment_per_prefix() {
+        let report = report(&cache(), &book());
+        let [chain] = &report[..] else {
+            panic!("expected a single chain");
+        };
+        let profit = |segment: &Option<Segment>| segment.as_ref().unwrap().entry.profit;
+
+        assert_eq!(
+            chain.items,
+            [
+                "Grimy ranarr weed",
+                "Ranarr weed",
+                "Ranarr potion (unf)",
+                "Prayer potion(3)"
+            ]
+        );
+        // Each sold at its high price less 2% tax
+        let stages: Vec<_> = chain.stages.iter().map(profit).collect();
+        assert_eq!(stages, [468_000, 356_000, 3_490_000]);
+
+        // Cleaning alone, then all the way to the unfinished potion as that
+        // makes more than either step
+        assert_eq!(profit(&chain.best[0]), 468_000);
+        let unf = chain.best[1].as_ref().unwrap();
+        assert_eq!(
+            (unf.buy.as_str(), unf.sell.as_str()),
+            ("Grimy ranarr weed", "Ranarr potion (unf)")
+        );
+        assert_eq!(unf.entry.profit, 856_000);
+        assert_eq!(profit(&chain.best[2]), 4_186_000);
+        assert_eq!(profit(&chain.whole), 4_186_000);
+    }
+}
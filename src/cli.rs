@@ -0,0 +1,399 @@
+//! Command line interface: the subcommands and the options they share.
+//!
+//! Running without a subcommand is the same as running `report`.
+
+use std::{path::PathBuf, time::Duration};
+
+use clap::{
+    error::ErrorKind, parser::ValueSource, Args, CommandFactory, FromArgMatches, Parser, Subcommand,
+};
+use eyre::eyre;
+use regex::Regex;
+
+use crate::{
+    cache::{self, GameMode, MaxAge, PriceSource},
+    config::Api,
+    filter::Filter,
+    flip::Criteria,
+    history::Timestep,
+    http::{Client, Fixtures, Offline, Ureq},
+    output::Format,
+    plan,
+    rank::SortBy,
+    recipe::RecipeBook,
+};
+
+/// Find profitable money making methods on the Grand Exchange
+#[derive(Debug, Parser)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options of the report printed when no subcommand is given
+    #[command(flatten)]
+    pub report: ReportArgs,
+
+    #[command(flatten)]
+    pub global: GlobalArgs,
+}
+
+impl Cli {
+    /// Parse the command line. The report options before a subcommand only
+    /// apply when there is no subcommand, so giving both is an error rather
+    /// than silently ignoring them.
+    pub fn parse_args() -> Self {
+        let mut command = <Self as CommandFactory>::command();
+        let matches = command.get_matches_mut();
+        if matches.subcommand().is_some() {
+            let report = ReportArgs::augment_args(clap::Command::new("report"));
+            let ignored = report.get_arguments().find(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            });
+            if let Some(arg) = ignored {
+                command
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        format!(
+                            "--{} only applies without a subcommand, give it after the \
+                             subcommand instead",
+                            arg.get_long().unwrap_or_default()
+                        ),
+                    )
+                    .exit();
+            }
+        }
+        Self::from_arg_matches(&matches).unwrap_or_else(|err| err.exit())
+    }
+
+    /// The subcommand to run, `report` if none was given
+    pub fn command(&self) -> Command {
+        self.command
+            .clone()
+            .unwrap_or_else(|| Command::Report(self.report.clone()))
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Evaluate every recipe against the current prices
+    Report(ReportArgs),
+    /// Show the prices, volume and limit of an item and the recipes using it
+    Item {
+        /// name or id of the item, e.g. "Ranarr weed"
+        query: String,
+    },
+    /// Fetch and inspect the stored price history
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Manage the cached API responses
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Plan which methods to do with a limited bankroll
+    Plan {
+        /// gp available to spend, e.g. 50m or 1.5b
+        #[arg(long, value_parser = plan::parse_gp)]
+        gp: usize,
+
+        #[command(flatten)]
+        report: ReportArgs,
+    },
+    /// Evaluate chains of recipes done one after another, e.g. cleaning a grimy
+    /// herb and making an unfinished potion with it
+    Chains,
+    /// Find items to flip across the whole GE
+    Flip(FlipArgs),
+    /// List the loaded recipes
+    Recipes,
+    /// Check every recipe against the item mapping
+    Validate {
+        /// check against this item mapping, as returned by the `/mapping`
+        /// endpoint, instead of the cached one
+        #[arg(long, value_name = "FILE")]
+        mapping: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// Fetch the price history of every item used by the recipes
+    Sync,
+    /// Print the stored price history of an item
+    Show {
+        /// name or id of the item
+        query: String,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Show the age, item count and size of every dataset
+    Status,
+    /// Fetch datasets again regardless of their age
+    Refresh {
+        /// datasets to fetch, every dataset if none are given
+        #[arg(value_name = "DATASET")]
+        datasets: Vec<String>,
+    },
+    /// Remove stored datasets
+    Clear {
+        /// datasets to remove, every dataset if none are given
+        #[arg(value_name = "DATASET")]
+        datasets: Vec<String>,
+    },
+    /// Write the stored datasets to a single file
+    Export {
+        file: PathBuf,
+        /// datasets to export, every stored dataset if none are given
+        #[arg(value_name = "DATASET")]
+        datasets: Vec<String>,
+    },
+    /// Store the datasets of a file written by `cache export`. They keep the
+    /// time they were originally fetched at.
+    Import { file: PathBuf },
+}
+
+/// Options shared by every subcommand
+#[derive(Clone, Debug, Args)]
+pub struct GlobalArgs {
+    /// query latest prices regardless of cache
+    #[arg(short, long, global = true, default_value_t = false)]
+    pub force: bool,
+
+    /// which prices to evaluate margins against
+    #[arg(short, long, global = true, value_enum, default_value_t = PriceSource::Latest)]
+    pub source: PriceSource,
+
+    /// resolution of the price history used by `history` and `--at`
+    #[arg(long, global = true, value_enum, default_value_t = Timestep::FiveMinute)]
+    pub timestep: Timestep,
+
+    /// how to print the output
+    #[arg(long, global = true, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+
+    /// which game mode's economy to use, each with its own cache
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = GameMode::Osrs,
+        env = "OSRS_MM_GAME_MODE"
+    )]
+    pub game_mode: GameMode,
+
+    /// store cached API responses and price history in this directory
+    #[arg(long, global = true, value_name = "DIR", env = "OSRS_MM_CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// never fetch from the API, using cached data however old it is
+    #[arg(long, global = true, default_value_t = false)]
+    pub offline: bool,
+
+    /// serve API responses from the JSON files in this directory, e.g.
+    /// `latest.json`, instead of fetching them
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "offline")]
+    pub fixtures: Option<PathBuf>,
+
+    /// read the config from this file instead of the default location
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// load extra recipes from a .toml or .json file
+    #[arg(short, long, global = true, value_name = "FILE")]
+    pub recipes: Vec<PathBuf>,
+
+    /// don't use the built-in recipes
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_default_recipes: bool,
+
+    /// reuse cached prices for up to this many seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    pub prices_max_age: Option<u64>,
+
+    /// reuse cached daily volumes for up to this many seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    pub volumes_max_age: Option<u64>,
+
+    /// reuse the cached item mapping for up to this many seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    pub mapping_max_age: Option<u64>,
+}
+
+impl GlobalArgs {
+    pub fn max_age(&self) -> MaxAge {
+        let mut max_age = MaxAge::default();
+        if self.force {
+            max_age.prices = Duration::ZERO;
+            max_age.volumes = Duration::ZERO;
+        }
+        if let Some(secs) = self.prices_max_age {
+            max_age.prices = Duration::from_secs(secs);
+        }
+        if let Some(secs) = self.volumes_max_age {
+            max_age.volumes = Duration::from_secs(secs);
+        }
+        if let Some(secs) = self.mapping_max_age {
+            max_age.mapping = Duration::from_secs(secs);
+        }
+        max_age
+    }
+
+    /// The cache directory of the game mode, under `$XDG_CACHE_HOME/osrs-mm`
+    /// unless one was given
+    pub fn cache_dir(&self) -> eyre::Result<PathBuf> {
+        let dir = match &self.cache_dir {
+            Some(dir) => dir.clone(),
+            None => cache::default_dir()
+                .ok_or_else(|| eyre!("no cache directory found, set --cache-dir or $HOME"))?,
+        };
+        Ok(dir.join(self.game_mode.as_str()))
+    }
+
+    /// How to fetch from the API
+    pub fn client(&self, api: &Api) -> Box<dyn Client> {
+        let base_url = api.url(self.game_mode);
+        match &self.fixtures {
+            _ if self.offline => Box::new(Offline { base_url }),
+            Some(dir) => Box::new(Fixtures {
+                dir: dir.clone(),
+                base_url,
+            }),
+            None => Box::new(Ureq::new(api, self.game_mode)),
+        }
+    }
+
+    pub fn recipes(&self) -> eyre::Result<RecipeBook> {
+        let mut book = if self.no_default_recipes {
+            RecipeBook::default()
+        } else {
+            RecipeBook::builtin()?
+        };
+        for path in &self.recipes {
+            book.extend(RecipeBook::from_file(path)?);
+        }
+        Ok(book)
+    }
+}
+
+/// Options deciding which methods are evaluated and how they are ordered
+#[derive(Clone, Debug, Args)]
+pub struct ReportArgs {
+    /// evaluate margins against the stored price history at this unix timestamp.
+    /// Only items used by recipes have a history, so high alchemy is limited to
+    /// those
+    #[arg(long, value_name = "TIMESTAMP")]
+    pub at: Option<usize>,
+
+    /// how to order the report
+    #[arg(long, value_enum, default_value_t = SortBy::Profit)]
+    pub sort_by: SortBy,
+
+    /// only show methods making at least this much profit
+    #[arg(long, value_name = "GP", allow_negative_numbers = true)]
+    pub min_profit: Option<isize>,
+
+    /// only show methods needing at most this much gp
+    #[arg(long, value_name = "GP")]
+    pub max_capital: Option<usize>,
+
+    /// only show methods selling items with at least this daily volume
+    #[arg(long)]
+    pub min_volume: Option<usize>,
+
+    /// only show methods with at least this return on investment, 0.05 being 5%
+    #[arg(long, allow_negative_numbers = true)]
+    pub min_roi: Option<f64>,
+
+    /// only show methods in this category
+    #[arg(long = "category", value_name = "CATEGORY")]
+    pub include: Vec<String>,
+
+    /// hide methods in this category
+    #[arg(long = "exclude-category", value_name = "CATEGORY")]
+    pub exclude: Vec<String>,
+
+    /// only show methods whose name matches this regex
+    #[arg(long, value_name = "REGEX")]
+    pub name: Option<Regex>,
+}
+
+impl ReportArgs {
+    pub fn filter(&self) -> Filter {
+        Filter {
+            min_profit: self.min_profit,
+            max_capital: self.max_capital,
+            min_volume: self.min_volume,
+            min_roi: self.min_roi,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct FlipArgs {
+    /// ignore prices that haven't traded for this many seconds, plus the
+    /// length of the window with `--source 5m` or `1h`
+    #[arg(long, value_name = "SECS", default_value_t = 30 * 60)]
+    pub max_quote_age: u64,
+
+    /// ignore items with less than this daily volume
+    #[arg(long, default_value_t = 10_000)]
+    pub min_volume: usize,
+
+    /// ignore items making less than this much per item after tax
+    #[arg(
+        long,
+        value_name = "GP",
+        default_value_t = 1,
+        allow_negative_numbers = true
+    )]
+    pub min_margin: isize,
+
+    /// ignore margins above this return on investment, 0.3 being 30%, as they
+    /// are likely manipulated
+    #[arg(long, default_value_t = 0.3)]
+    pub max_roi: f64,
+
+    /// how many items to show
+    #[arg(long, default_value_t = 25)]
+    pub top: usize,
+}
+
+impl FlipArgs {
+    pub fn criteria(&self, source: PriceSource) -> Criteria {
+        Criteria {
+            max_quote_age: self.max_quote_age,
+            window: source.window().unwrap_or_default(),
+            min_volume: self.min_volume,
+            min_margin: self.min_margin,
+            max_roi: self.max_roi,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn max_age(args: &[&str]) -> MaxAge {
+        let cli = Cli::try_parse_from(["osrs-mm"].iter().chain(args)).unwrap();
+        cli.global.max_age()
+    }
+
+    #[test]
+    fn force_refetches_prices_and_volumes() {
+        let max_age = max_age(&["--force"]);
+        assert_eq!(max_age.prices, Duration::ZERO);
+        assert_eq!(max_age.volumes, Duration::ZERO);
+        assert_eq!(max_age.mapping, MaxAge::default().mapping);
+
+        // A max age given along with it wins
+        let max_age = self::max_age(&["--force", "--prices-max-age", "30"]);
+        assert_eq!(max_age.prices, Duration::from_secs(30));
+        assert_eq!(max_age.volumes, Duration::ZERO);
+    }
+}
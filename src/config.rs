@@ -0,0 +1,129 @@
+//! User configuration loaded from a TOML file.
+//!
+//! The file is looked up at `$XDG_CONFIG_HOME/osrs-mm/config.toml`, falling
+//! back to `~/.config/osrs-mm/config.toml`, unless a path is given explicitly.
+//! Anything missing from the file keeps its default value, including the
+//! default filters which the `[filter]` table is merged into. The `[api]`
+//! settings can also be set with the `OSRS_MM_BASE_URL` and
+//! `OSRS_MM_USER_AGENT` environment variables, which win over the file.
+//!
+//! ```toml
+//! [api]
+//! user_agent = "osrs-mm/0.1.0 (@me on Discord)"
+//!
+//! [filter]
+//! min_profit = 500_000
+//! exclude = ["cooking"]
+//!
+//! [filter.category.decanting]
+//! min_volume = 100_000
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use eyre::{Result, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+    cache::{GameMode, DEFAULT_API_ROOT},
+    filter::Filters,
+};
+
+/// How to talk to the price API
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Api {
+    /// Url the endpoints are fetched from, e.g. a local mirror, used as is
+    /// whatever the game mode. Defaults to the wiki's API of the selected game
+    /// mode.
+    pub base_url: Option<String>,
+    /// Sent with every request. The API asks for a description of the tool
+    /// along with a way to contact you.
+    pub user_agent: String,
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            user_agent: concat!("osrs-mm/", env!("CARGO_PKG_VERSION")).to_string(),
+        }
+    }
+}
+
+impl Api {
+    /// Url of the endpoints of `mode`, without a trailing slash
+    pub fn url(&self, mode: GameMode) -> String {
+        match &self.base_url {
+            Some(base_url) => base_url.trim_end_matches('/').to_string(),
+            None => format!("{DEFAULT_API_ROOT}/{}", mode.as_str()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub api: Api,
+    pub filter: Filters,
+}
+
+impl Config {
+    /// Load the config at `path`, or at the default location when `None`. A
+    /// missing file at the default location gives the default config.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = Self::from_file(path)?;
+        if let Ok(base_url) = std::env::var("OSRS_MM_BASE_URL") {
+            config.api.base_url = Some(base_url);
+        }
+        if let Ok(user_agent) = std::env::var("OSRS_MM_USER_AGENT") {
+            config.api.user_agent = user_agent;
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("failed to read config {}", path.display()))?;
+        let mut config: Self = toml::from_str(&contents)
+            .wrap_err_with(|| format!("invalid config {}", path.display()))?;
+        config.filter = Filters::default().merged(&config.filter);
+        Ok(config)
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("osrs-mm").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_url() {
+        let api = Api::default();
+        assert_eq!(
+            api.url(GameMode::Dmm),
+            "https://prices.runescape.wiki/api/v1/dmm"
+        );
+
+        let mirror = Api {
+            base_url: Some("http://localhost:8080/osrs/".to_string()),
+            ..api
+        };
+        assert_eq!(mirror.url(GameMode::Osrs), "http://localhost:8080/osrs");
+        assert_eq!(mirror.url(GameMode::Fsw), "http://localhost:8080/osrs");
+    }
+}
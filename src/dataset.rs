@@ -0,0 +1,319 @@
+//! A single endpoint of the price API kept in the cache directory.
+//!
+//! The raw response body is stored as is, next to a metadata file recording
+//! when and where it was fetched from. A stale dataset is fetched again with
+//! its ETag, so an unchanged response costs the API nothing but a `304`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{cache::write_atomic, http::Client};
+
+/// Version of the files a dataset is stored as. Bumped whenever they change in
+/// a way that older files can't be read anymore, which makes them stale.
+pub const SCHEMA_VERSION: usize = 1;
+
+/// Stored alongside a dataset in `<file>.meta.json`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Unix timestamp of when the dataset was last fetched or revalidated
+    pub fetched_at: u64,
+    pub source_url: String,
+    /// ETag of the response, sent back to only fetch the dataset when it changed
+    pub etag: Option<String>,
+    pub schema_version: usize,
+}
+
+impl Metadata {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub struct Dataset<T> {
+    /// Short name of the dataset, e.g. `mapping`
+    pub name: &'static str,
+    pub url: String,
+    pub filename: String,
+    pub max_age: Duration,
+    parse: fn(&str) -> Result<T>,
+}
+
+impl<T> Dataset<T> {
+    pub fn new(
+        name: &'static str,
+        url: String,
+        filename: &str,
+        max_age: Duration,
+        parse: fn(&str) -> Result<T>,
+    ) -> Self {
+        Self {
+            name,
+            url,
+            filename: filename.to_string(),
+            max_age,
+            parse,
+        }
+    }
+
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(&self.filename)
+    }
+
+    fn metadata_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.meta.json", self.filename))
+    }
+
+    /// Metadata of the stored dataset, if there is one
+    pub fn metadata(&self, dir: &Path) -> Option<Metadata> {
+        let contents = std::fs::read_to_string(self.metadata_path(dir)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Whether the stored dataset can be used without fetching it again.
+    /// Datasets stored without metadata are as old as their file.
+    pub fn is_fresh(&self, dir: &Path) -> bool {
+        let age = match self.metadata(dir) {
+            Some(meta) if meta.schema_version != SCHEMA_VERSION || meta.source_url != self.url => {
+                return false
+            }
+            Some(meta) => Some(meta.age()),
+            None => std::fs::metadata(self.path(dir))
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok()),
+        };
+        age.is_some_and(|age| age <= self.max_age)
+    }
+
+    /// Parse the stored dataset regardless of its age
+    pub fn read(&self, dir: &Path) -> Result<T> {
+        let path = self.path(dir);
+        let contents = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        (self.parse)(&contents).wrap_err_with(|| format!("invalid {}", path.display()))
+    }
+
+    /// Store `body` as the dataset along with its metadata
+    pub fn write(&self, dir: &Path, body: &str, etag: Option<String>) -> Result<()> {
+        write_atomic(&self.path(dir), body.as_bytes())?;
+        self.write_metadata(dir, etag)
+    }
+
+    fn write_metadata(&self, dir: &Path, etag: Option<String>) -> Result<()> {
+        let metadata = Metadata {
+            fetched_at: now(),
+            source_url: self.url.clone(),
+            etag,
+            schema_version: SCHEMA_VERSION,
+        };
+        let contents = serde_json::to_string_pretty(&metadata)?;
+        write_atomic(&self.metadata_path(dir), contents.as_bytes())
+    }
+
+    /// Remove the stored dataset and its metadata
+    pub fn clear(&self, dir: &Path) -> Result<()> {
+        for path in [self.path(dir), self.metadata_path(dir)] {
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(err)
+                        .wrap_err_with(|| format!("failed to remove {}", path.display()))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetch the dataset and store it, unless the API says the stored one is
+    /// still current
+    pub fn fetch(&self, dir: &Path, client: &dyn Client) -> Result<T> {
+        let stored = self
+            .path(dir)
+            .exists()
+            .then(|| self.metadata(dir))
+            .flatten();
+        let etag = stored
+            .filter(|meta| meta.schema_version == SCHEMA_VERSION && meta.source_url == self.url)
+            .and_then(|meta| meta.etag);
+
+        match client.get(&self.url, etag.as_deref())? {
+            Some(response) => {
+                let data = (self.parse)(&response.body).wrap_err_with(|| {
+                    format!("invalid {} response from {}", self.name, self.url)
+                })?;
+                self.write(dir, &response.body, response.etag)?;
+                Ok(data)
+            }
+            None => {
+                self.write_metadata(dir, etag)?;
+                self.read(dir)
+            }
+        }
+    }
+
+    /// The stored dataset if it is fresh and readable, otherwise a fetched one.
+    /// An offline client always gets the stored dataset, however old it is.
+    pub fn load(&self, dir: &Path, client: &dyn Client) -> Result<T> {
+        if client.offline() {
+            return self.read(dir).wrap_err_with(|| {
+                format!(
+                    "the {} dataset isn't cached and can't be fetched offline",
+                    self.name
+                )
+            });
+        }
+
+        if self.is_fresh(dir) {
+            if let Ok(data) = self.read(dir) {
+                return Ok(data);
+            }
+        }
+        self.fetch(dir, client)
+    }
+}
+
+/// A dataset regardless of what it parses into, for managing the cache
+pub trait Stored {
+    fn name(&self) -> &'static str;
+    fn url(&self) -> &str;
+    fn path(&self, dir: &Path) -> PathBuf;
+    fn metadata(&self, dir: &Path) -> Option<Metadata>;
+    /// Number of items in the stored dataset
+    fn count(&self, dir: &Path) -> Result<usize>;
+    /// Fetch the dataset regardless of its age. Returns the number of items.
+    fn refresh(&self, dir: &Path, client: &dyn Client) -> Result<usize>;
+    fn clear(&self, dir: &Path) -> Result<()>;
+    /// Store a dataset exported from another cache, keeping its metadata
+    fn import(&self, dir: &Path, body: &str, metadata: &Metadata) -> Result<()>;
+}
+
+impl<V> Stored for Dataset<HashMap<usize, V>> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn path(&self, dir: &Path) -> PathBuf {
+        Dataset::path(self, dir)
+    }
+
+    fn metadata(&self, dir: &Path) -> Option<Metadata> {
+        Dataset::metadata(self, dir)
+    }
+
+    fn count(&self, dir: &Path) -> Result<usize> {
+        Ok(self.read(dir)?.len())
+    }
+
+    fn refresh(&self, dir: &Path, client: &dyn Client) -> Result<usize> {
+        Ok(self.fetch(dir, client)?.len())
+    }
+
+    fn clear(&self, dir: &Path) -> Result<()> {
+        Dataset::clear(self, dir)
+    }
+
+    fn import(&self, dir: &Path, body: &str, metadata: &Metadata) -> Result<()> {
+        (self.parse)(body).wrap_err_with(|| format!("invalid {} dataset", self.name))?;
+        write_atomic(&Dataset::path(self, dir), body.as_bytes())?;
+        let contents = serde_json::to_string_pretty(metadata)?;
+        write_atomic(&self.metadata_path(dir), contents.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    const URL: &str = "https://prices.runescape.wiki/api/v1/osrs/latest";
+
+    /// An empty directory only used by `test`
+    fn dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("osrs-mm-{}-{test}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn dataset(max_age: u64) -> Dataset<HashMap<usize, usize>> {
+        Dataset::new(
+            "prices",
+            URL.to_string(),
+            "prices.json",
+            Duration::from_secs(max_age),
+            |body| Ok(serde_json::from_str(body)?),
+        )
+    }
+
+    /// Store a dataset fetched `age` seconds ago from `url`
+    fn store(dir: &Path, age: u64, url: &str, schema_version: usize) {
+        std::fs::write(dir.join("prices.json"), "{}").unwrap();
+        let metadata = format!(
+            r#"{{
+                "fetched_at": {},
+                "source_url": "{url}",
+                "etag": null,
+                "schema_version": {schema_version}
+            }}"#,
+            now() - age
+        );
+        std::fs::write(dir.join("prices.json.meta.json"), metadata).unwrap();
+    }
+
+    #[test]
+    fn fresh_until_max_age() {
+        let dir = dir("fresh_until_max_age");
+        assert!(!dataset(60).is_fresh(&dir));
+
+        store(&dir, 100, URL, SCHEMA_VERSION);
+        assert!(dataset(200).is_fresh(&dir));
+        assert!(!dataset(60).is_fresh(&dir));
+    }
+
+    #[test]
+    fn stale_from_another_url_or_schema() {
+        let dir = dir("stale_from_another_url_or_schema");
+        store(&dir, 0, URL, SCHEMA_VERSION);
+        assert!(dataset(60).is_fresh(&dir));
+
+        store(&dir, 0, "https://example.com/latest", SCHEMA_VERSION);
+        assert!(!dataset(60).is_fresh(&dir));
+        store(&dir, 0, URL, SCHEMA_VERSION + 1);
+        assert!(!dataset(60).is_fresh(&dir));
+    }
+
+    #[test]
+    fn as_old_as_the_file_without_metadata() {
+        let dir = dir("as_old_as_the_file_without_metadata");
+        std::fs::write(dir.join("prices.json"), "{}").unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(dir.join("prices.json"))
+            .unwrap()
+            .set_modified(two_hours_ago)
+            .unwrap();
+
+        assert!(dataset(3 * 60 * 60).is_fresh(&dir));
+        assert!(!dataset(60 * 60).is_fresh(&dir));
+    }
+}
@@ -0,0 +1,231 @@
+//! Filters deciding which methods end up in the report.
+//!
+//! A global [`Filter`] applies to every method and can be overridden per recipe
+//! category. Any field left unset doesn't filter anything.
+
+use std::collections::{BTreeMap, HashMap};
+
+use regex::Regex;
+use serde::{de::IgnoredAny, Deserialize, Deserializer};
+
+use crate::makers::ReportEntry;
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Regex>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| Regex::new(&pattern).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filter {
+    /// Smallest profit of using the whole limit
+    pub min_profit: Option<isize>,
+    /// Largest amount of gp needed to use the whole limit
+    pub max_capital: Option<usize>,
+    /// Smallest daily volume of the item being sold
+    pub min_volume: Option<usize>,
+    /// Smallest profit as a fraction of the cost, `0.05` being 5%
+    pub min_roi: Option<f64>,
+    /// Only keep methods in these categories
+    pub include: Vec<String>,
+    /// Drop methods in these categories
+    pub exclude: Vec<String>,
+    /// Only keep methods whose name matches this regex
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub name: Option<Regex>,
+}
+
+impl Filter {
+    /// Combine with `other`, where every field set in `other` wins
+    pub fn merged(&self, other: &Filter) -> Filter {
+        Filter {
+            min_profit: other.min_profit.or(self.min_profit),
+            max_capital: other.max_capital.or(self.max_capital),
+            min_volume: other.min_volume.or(self.min_volume),
+            min_roi: other.min_roi.or(self.min_roi),
+            include: if other.include.is_empty() {
+                self.include.clone()
+            } else {
+                other.include.clone()
+            },
+            exclude: if other.exclude.is_empty() {
+                self.exclude.clone()
+            } else {
+                other.exclude.clone()
+            },
+            name: other.name.clone().or_else(|| self.name.clone()),
+        }
+    }
+
+    pub fn keep(&self, entry: &ReportEntry) -> bool {
+        self.min_profit.is_none_or(|min| entry.profit >= min)
+            && self.max_capital.is_none_or(|max| entry.cost <= max)
+            && self.min_volume.is_none_or(|min| entry.volume >= min)
+            && self.min_roi.is_none_or(|min| entry.roi() >= min)
+            && (self.include.is_empty() || self.include.contains(&entry.category))
+            && !self.exclude.contains(&entry.category)
+            && self.name.as_ref().is_none_or(|re| re.is_match(&entry.name))
+    }
+}
+
+/// The global filter and its per category overrides
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "FilterTable")]
+pub struct Filters {
+    pub global: Filter,
+    pub category: HashMap<String, Filter>,
+    /// Set from the command line, wins over the global and category filters
+    #[serde(skip)]
+    pub overrides: Filter,
+}
+
+/// The `[filter]` table as written. `deny_unknown_fields` has no effect on a
+/// flattened struct, so whatever the global filter doesn't take is collected
+/// in `unknown` and rejected.
+#[derive(Deserialize)]
+struct FilterTable {
+    #[serde(flatten)]
+    global: Filter,
+    #[serde(default)]
+    category: HashMap<String, Filter>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+impl TryFrom<FilterTable> for Filters {
+    type Error = String;
+
+    fn try_from(table: FilterTable) -> Result<Self, Self::Error> {
+        if let Some(field) = table.unknown.keys().next() {
+            return Err(format!("unknown field `{field}` in [filter]"));
+        }
+        Ok(Filters {
+            global: table.global,
+            category: table.category,
+            overrides: Filter::default(),
+        })
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            global: Filter {
+                min_profit: Some(1_000_000),
+                ..Default::default()
+            },
+            category: HashMap::from([
+                (
+                    "cleaning".to_string(),
+                    Filter {
+                        max_capital: Some(20_000_000),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    // Only care about potions that will have some movement on the ge
+                    "decanting".to_string(),
+                    Filter {
+                        min_volume: Some(100_000),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    // Every item with a high alch value is tried, most of which
+                    // barely trade and have a stale low price
+                    "alching".to_string(),
+                    Filter {
+                        min_volume: Some(10_000),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            overrides: Filter::default(),
+        }
+    }
+}
+
+impl Filters {
+    /// Combine with `other`, where every field and category set in `other` wins
+    pub fn merged(&self, other: &Filters) -> Filters {
+        let mut category = self.category.clone();
+        for (name, filter) in &other.category {
+            let merged = match category.get(name) {
+                Some(current) => current.merged(filter),
+                None => filter.clone(),
+            };
+            category.insert(name.clone(), merged);
+        }
+
+        Filters {
+            global: self.global.merged(&other.global),
+            category,
+            overrides: self.overrides.merged(&other.overrides),
+        }
+    }
+
+    /// The filter used for methods in `category`
+    pub fn for_category(&self, category: &str) -> Filter {
+        match self.category.get(category) {
+            Some(filter) => self.global.merged(filter),
+            None => self.global.clone(),
+        }
+        .merged(&self.overrides)
+    }
+
+    /// Drop every entry that doesn't pass the filter of its category
+    pub fn apply(&self, entries: &mut Vec<ReportEntry>) {
+        entries.retain(|entry| self.for_category(&entry.category).keep(entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = toml::from_str::<Filters>("min_proft = 5").unwrap_err();
+        assert!(err.to_string().contains("min_proft"), "{err}");
+        assert!(toml::from_str::<Filters>("[category.cooking]\nmin_volme = 5").is_err());
+        assert!(toml::from_str::<Filters>("min_profit = 5").is_ok());
+    }
+
+    #[test]
+    fn merge_order() {
+        let file: Filters = toml::from_str(
+            r#"
+            min_profit = 500_000
+            min_volume = 10
+            exclude = ["cooking"]
+
+            [category.cleaning]
+            min_profit = 2_000_000
+            "#,
+        )
+        .unwrap();
+        let mut filters = Filters::default().merged(&file);
+        filters.overrides = Filter {
+            min_volume: Some(20),
+            ..Default::default()
+        };
+
+        // The file wins over the defaults
+        let smithing = filters.for_category("smithing");
+        assert_eq!(smithing.min_profit, Some(500_000));
+        assert_eq!(smithing.exclude, ["cooking"]);
+        // A category wins over the global filter, and keeps the defaults of
+        // that category the file doesn't set
+        let cleaning = filters.for_category("cleaning");
+        assert_eq!(cleaning.min_profit, Some(2_000_000));
+        assert_eq!(cleaning.max_capital, Some(20_000_000));
+        // The command line wins over everything
+        assert_eq!(smithing.min_volume, Some(20));
+        assert_eq!(cleaning.min_volume, Some(20));
+        filters.overrides.min_profit = Some(0);
+        assert_eq!(filters.for_category("cleaning").min_profit, Some(0));
+    }
+}
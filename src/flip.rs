@@ -0,0 +1,228 @@
+//! Flipping: buying an item with a buy offer and selling it with a sell offer.
+//!
+//! Every item with both a low and a high price is a candidate. Quotes that
+//! haven't traded recently, thinly traded items and spreads too wide to be
+//! real are left out, as they are the easiest to manipulate. The rest are
+//! ranked by the margin that can be expected over a single limit window.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::{cache::Cache, rank::window_volume, tax};
+
+/// What a quote has to meet to be considered
+#[derive(Copy, Clone, Debug)]
+pub struct Criteria {
+    /// Oldest a low or high price can be, in seconds
+    pub max_quote_age: u64,
+    /// Length of the window averaged prices cover, in seconds, 0 for the latest
+    /// prices. Averages are only published once their window ends, so the
+    /// newest one can be this much older than `max_quote_age`.
+    pub window: u64,
+    /// Smallest daily volume of the item
+    pub min_volume: usize,
+    /// Smallest margin per item after tax
+    pub min_margin: isize,
+    /// Largest margin as a fraction of the buy price. Wider spreads are usually
+    /// a single odd trade or an item being manipulated.
+    pub max_roi: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Flip {
+    pub id: usize,
+    pub name: String,
+    /// What a buy offer fills at, the low price
+    pub buy: usize,
+    /// What a sell offer fills at, the high price
+    pub sell: usize,
+    /// GE tax paid on selling a single item
+    pub tax: usize,
+    /// Profit of flipping a single item after tax
+    pub margin: isize,
+    pub roi: f64,
+    pub limit: usize,
+    pub volume: usize,
+    /// Items sold at the low price, filling buy offers. Only known for averaged
+    /// prices.
+    pub buy_volume: Option<usize>,
+    /// Items bought at the high price, filling sell offers. Only known for
+    /// averaged prices.
+    pub sell_volume: Option<usize>,
+    /// Seconds since the low price was last traded
+    pub buy_age: u64,
+    /// Seconds since the high price was last traded
+    pub sell_age: u64,
+    /// Items that can be expected to be bought within a limit window
+    pub fillable: usize,
+    /// Profit of flipping every fillable item
+    pub expected_profit: isize,
+}
+
+/// Every item worth flipping according to `criteria`, best first
+pub fn scan(cache: &Cache, criteria: &Criteria) -> eyre::Result<Vec<Flip>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(scan_at(cache, criteria, now))
+}
+
+/// Every item worth flipping at unix time `now`, best first
+fn scan_at(cache: &Cache, criteria: &Criteria, now: u64) -> Vec<Flip> {
+    let mut flips: Vec<Flip> = cache
+        .items
+        .keys()
+        .filter_map(|&id| flip(cache, id, now))
+        .filter(|flip| keep(flip, criteria))
+        .collect();
+    flips.sort_by(|a, b| {
+        b.expected_profit
+            .cmp(&a.expected_profit)
+            .then(a.id.cmp(&b.id))
+    });
+    flips
+}
+
+/// The flip of item `id`, if it has both prices and a buy limit
+fn flip(cache: &Cache, id: usize, now: u64) -> Option<Flip> {
+    let item = cache.get(id).ok()?;
+    let price = item.price?;
+    let (buy, sell) = (price.low?, price.high?);
+    let limit = item.limit().ok()?;
+    let age = |time: Option<usize>| now.saturating_sub(time.unwrap_or_default() as u64);
+
+    let tax = tax::per_item(id, sell);
+    let margin = sell as isize - tax as isize - buy as isize;
+    let fillable = limit.min(window_volume(item.volume));
+    Some(Flip {
+        id,
+        name: item.item.name.clone(),
+        buy,
+        sell,
+        tax,
+        margin,
+        roi: if buy == 0 {
+            0.0
+        } else {
+            margin as f64 / buy as f64
+        },
+        limit,
+        volume: item.volume,
+        buy_volume: price.low_volume,
+        sell_volume: price.high_volume,
+        buy_age: age(price.low_time),
+        sell_age: age(price.high_time),
+        fillable,
+        expected_profit: margin * fillable as isize,
+    })
+}
+
+fn keep(flip: &Flip, criteria: &Criteria) -> bool {
+    let max_age = criteria.max_quote_age + criteria.window;
+    flip.buy_age <= max_age
+        && flip.sell_age <= max_age
+        && flip.volume >= criteria.min_volume
+        && flip.margin >= criteria.min_margin
+        && flip.roi <= criteria.max_roi
+        // Averaged prices nobody traded at on one side aren't a real spread
+        && flip.buy_volume.is_none_or(|volume| volume > 0)
+        && flip.sell_volume.is_none_or(|volume| volume > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRITERIA: Criteria = Criteria {
+        max_quote_age: 1_800,
+        window: 0,
+        min_volume: 10_000,
+        min_margin: 1,
+        max_roi: 0.3,
+    };
+
+    /// Quotes traded at time 0
+    fn cache() -> Cache {
+        Cache::default()
+            // 78 gp margin, but only 100 can be bought
+            .with_item(1, "Dear", 100, (1_000, 1_100), 60_000)
+            // 8 gp margin on 10,000
+            .with_item(2, "Cheap", 10_000, (100, 110), 600_000)
+            // 96% return, too good to be true
+            .with_item(3, "Wide", 10_000, (100, 200), 600_000)
+    }
+
+    fn ids(flips: &[Flip]) -> Vec<usize> {
+        flips.iter().map(|flip| flip.id).collect()
+    }
+
+    #[test]
+    fn ordered_by_expected_profit() {
+        let flips = scan_at(&cache(), &CRITERIA, 60);
+
+        assert_eq!(ids(&flips), [2, 1]);
+        assert_eq!(flips[0].margin, 8);
+        assert_eq!(flips[0].expected_profit, 80_000);
+        assert_eq!(flips[1].margin, 78);
+        assert_eq!(flips[1].expected_profit, 7_800);
+    }
+
+    #[test]
+    fn stale_quotes() {
+        let mut cache = cache();
+        assert_eq!(ids(&scan_at(&cache, &CRITERIA, 1_800)), [2, 1]);
+        assert!(scan_at(&cache, &CRITERIA, 1_801).is_empty());
+
+        // Both sides have to have traded recently
+        cache.prices.get_mut(&2).unwrap().low_time = Some(1_000);
+        assert!(scan_at(&cache, &CRITERIA, 1_801).is_empty());
+        cache.prices.get_mut(&2).unwrap().high_time = Some(1_000);
+        assert_eq!(ids(&scan_at(&cache, &CRITERIA, 1_801)), [2]);
+    }
+
+    #[test]
+    fn averaged_quotes() {
+        let mut cache = cache();
+        // The hour from 0 to 3,600
+        cache.prices = crate::cache::parse_average(
+            r#"{"timestamp": 0, "data": {"2": {
+                "avgHighPrice": 110, "highPriceVolume": 500,
+                "avgLowPrice": 100, "lowPriceVolume": 400
+            }}}"#,
+            3_600,
+        )
+        .unwrap();
+        let criteria = Criteria {
+            window: 3_600,
+            ..CRITERIA
+        };
+
+        let flips = scan_at(&cache, &criteria, 4_000);
+        assert_eq!(ids(&flips), [2]);
+        assert_eq!((flips[0].buy_age, flips[0].sell_age), (400, 400));
+        assert_eq!(ids(&scan_at(&cache, &criteria, 3_600 + 5_400)), [2]);
+        assert!(scan_at(&cache, &criteria, 3_600 + 5_401).is_empty());
+    }
+
+    #[test]
+    fn max_roi() {
+        let criteria = Criteria {
+            max_roi: 1.0,
+            ..CRITERIA
+        };
+        let flips = scan_at(&cache(), &criteria, 60);
+
+        assert_eq!(ids(&flips), [3, 2, 1]);
+        assert_eq!(flips[0].roi, 0.96);
+    }
+
+    #[test]
+    fn sides_nobody_traded() {
+        let mut cache = cache();
+        cache.prices.get_mut(&1).unwrap().low_volume = Some(0);
+        cache.prices.get_mut(&1).unwrap().high_volume = Some(5);
+        cache.prices.get_mut(&2).unwrap().low_volume = Some(5);
+        cache.prices.get_mut(&2).unwrap().high_volume = Some(5);
+
+        assert_eq!(ids(&scan_at(&cache, &CRITERIA, 60)), [2]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Local store of historical prices pulled from the `/timeseries` endpoint.
+//!
+//! Every item gets its own append-only file per timestep in
+//! `history/<timestep>/<id>.jsonl` of the cache directory, holding one
+//! [`PricePoint`] per line in timestamp order. Syncing only ever appends points
+//! newer than the last one stored, so old data is kept around after it drops
+//! out of the API's window.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    http::Client,
+    item::{AveragePrice, ItemPrice},
+};
+
+const HISTORY_DIRNAME: &str = "history";
+
+/// Resolution of a price history
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Timestep {
+    #[default]
+    #[value(name = "5m")]
+    FiveMinute,
+    #[value(name = "1h")]
+    OneHour,
+    #[value(name = "6h")]
+    SixHour,
+    #[value(name = "24h")]
+    OneDay,
+}
+
+impl Timestep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Timestep::FiveMinute => "5m",
+            Timestep::OneHour => "1h",
+            Timestep::SixHour => "6h",
+            Timestep::OneDay => "24h",
+        }
+    }
+}
+
+/// Averaged prices of an item for the window starting at `timestamp`
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct PricePoint {
+    pub timestamp: usize,
+    #[serde(flatten)]
+    pub price: AveragePrice,
+}
+
+/// Response for the `/timeseries` endpoint of the price API
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeseriesResponse {
+    pub item_id: usize,
+    pub data: Vec<PricePoint>,
+}
+
+pub struct History {
+    dir: PathBuf,
+    timestep: Timestep,
+}
+
+impl History {
+    pub fn new(cache_dir: &Path, timestep: Timestep) -> Self {
+        Self {
+            dir: cache_dir.join(HISTORY_DIRNAME).join(timestep.as_str()),
+            timestep,
+        }
+    }
+
+    fn path(&self, id: usize) -> PathBuf {
+        self.dir.join(format!("{id}.jsonl"))
+    }
+
+    /// Fetch the latest timeseries of every item in `ids` and store any new
+    /// points. Returns the number of points added.
+    pub fn sync(&self, client: &dyn Client, ids: &[usize]) -> Result<usize> {
+        let mut added = 0;
+        for id in ids {
+            let url = format!(
+                "{}/timeseries?id={}&timestep={}",
+                client.base_url(),
+                id,
+                self.timestep.as_str()
+            );
+            // Only a request with an ETag can come back as not modified
+            let response = client
+                .get(&url, None)?
+                .ok_or_else(|| eyre!("{url} answered not modified to a request without an ETag"))?;
+            let response: TimeseriesResponse = serde_json::from_str(&response.body)?;
+            if response.data.is_empty() {
+                eprintln!("warning: no timeseries returned for item {id}, skipping it");
+                continue;
+            }
+            added += self.append(*id, &response.data)?;
+        }
+        Ok(added)
+    }
+
+    /// Append all `points` newer than the last stored point of `id`. Returns the
+    /// number of points added.
+    pub fn append(&self, id: usize, points: &[PricePoint]) -> Result<usize> {
+        let last = self.load(id)?.last().map(|p| p.timestamp);
+        let mut points = points
+            .iter()
+            .filter(|p| last.is_none_or(|last| p.timestamp > last))
+            .collect::<Vec<_>>();
+        points.sort_by_key(|p| p.timestamp);
+        if points.is_empty() {
+            return Ok(0);
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(id))?;
+        for point in &points {
+            writeln!(file, "{}", serde_json::to_string(point)?)?;
+        }
+
+        Ok(points.len())
+    }
+
+    /// All stored points of `id` in timestamp order
+    pub fn load(&self, id: usize) -> Result<Vec<PricePoint>> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        std::fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    /// Price of `id` as it was at `timestamp`. Each side is taken from the most
+    /// recent point at or before `timestamp` where that side was traded.
+    pub fn price_at(&self, id: usize, timestamp: usize) -> Result<Option<ItemPrice>> {
+        let points = self.load(id)?;
+        let before = points.iter().rev().skip_while(|p| p.timestamp > timestamp);
+
+        let mut price: Option<ItemPrice> = None;
+        for point in before {
+            let price = price.get_or_insert_with(|| point.price.into_item_price(point.timestamp));
+            if price.high.is_none() && point.price.avg_high_price.is_some() {
+                price.high = point.price.avg_high_price;
+                price.high_time = Some(point.timestamp);
+            }
+            if price.low.is_none() && point.price.avg_low_price.is_some() {
+                price.low = point.price.avg_low_price;
+                price.low_time = Some(point.timestamp);
+            }
+            if price.high.is_some() && price.low.is_some() {
+                break;
+            }
+        }
+
+        Ok(price)
+    }
+
+    /// Snapshot of the prices of every item in `ids` at `timestamp`. Items
+    /// without any history before `timestamp` are left out.
+    pub fn prices_at(&self, ids: &[usize], timestamp: usize) -> Result<HashMap<usize, ItemPrice>> {
+        let mut prices = HashMap::new();
+        for id in ids {
+            if let Some(price) = self.price_at(*id, timestamp)? {
+                prices.insert(*id, price);
+            }
+        }
+        Ok(prices)
+    }
+}
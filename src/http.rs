@@ -0,0 +1,121 @@
+//! Clients for fetching from the price API.
+//!
+//! [`Ureq`] talks to the network, [`Fixtures`] serves recorded responses from
+//! disk so runs are reproducible, and [`Offline`] refuses every request.
+
+use std::path::PathBuf;
+
+use eyre::{bail, Result, WrapErr};
+
+use crate::{cache::GameMode, config::Api};
+
+/// A successful response of the price API
+pub struct Response {
+    pub body: String,
+    pub etag: Option<String>,
+}
+
+pub trait Client {
+    /// Url of the price API, without a trailing slash
+    fn base_url(&self) -> &str;
+
+    /// Fetch `url`. When `etag` is given and still matches, the response is
+    /// `None` as the stored copy is current.
+    fn get(&self, url: &str, etag: Option<&str>) -> Result<Option<Response>>;
+
+    /// Whether stored data has to be used however old it is
+    fn offline(&self) -> bool {
+        false
+    }
+}
+
+pub struct Ureq {
+    agent: ureq::Agent,
+    base_url: String,
+}
+
+impl Ureq {
+    pub fn new(api: &Api, mode: GameMode) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .user_agent(&api.user_agent)
+                .build(),
+            base_url: api.url(mode),
+        }
+    }
+}
+
+impl Client for Ureq {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(&self, url: &str, etag: Option<&str>) -> Result<Option<Response>> {
+        let mut request = self.agent.get(url);
+        if let Some(etag) = etag {
+            request = request.set("If-None-Match", etag);
+        }
+        let response = request
+            .call()
+            .wrap_err_with(|| format!("failed to fetch {url}"))?;
+        if response.status() == 304 {
+            return Ok(None);
+        }
+
+        let etag = response.header("ETag").map(str::to_string);
+        Ok(Some(Response {
+            body: response.into_string()?,
+            etag,
+        }))
+    }
+}
+
+/// Serves each url from a JSON file named after its last path segment and
+/// query, e.g. `latest.json` or `timeseries_id=2_timestep=5m.json`
+pub struct Fixtures {
+    pub dir: PathBuf,
+    pub base_url: String,
+}
+
+impl Fixtures {
+    fn path(&self, url: &str) -> PathBuf {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let mut name = path.rsplit('/').next().unwrap_or_default().to_string();
+        if !query.is_empty() {
+            name.push('_');
+            name.push_str(&query.replace('&', "_"));
+        }
+        self.dir.join(format!("{name}.json"))
+    }
+}
+
+impl Client for Fixtures {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(&self, url: &str, _etag: Option<&str>) -> Result<Option<Response>> {
+        let path = self.path(url);
+        let body = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("no fixture for {url} at {}", path.display()))?;
+        Ok(Some(Response { body, etag: None }))
+    }
+}
+
+pub struct Offline {
+    pub base_url: String,
+}
+
+impl Client for Offline {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(&self, url: &str, _etag: Option<&str>) -> Result<Option<Response>> {
+        bail!("refusing to fetch {url} in offline mode")
+    }
+
+    fn offline(&self) -> bool {
+        true
+    }
+}
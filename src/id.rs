@@ -1,29 +0,0 @@
-pub const ID_ASTRAL_RUNE: usize = 9075;
-pub const ID_BLACK_DRAGONHIDE: usize = 1747;
-pub const ID_BLACK_DRAGON_LEATHER: usize = 2509;
-pub const ID_BLOOD_RUNE: usize = 565;
-pub const ID_CLAY: usize = 434;
-pub const ID_COSMIC_RUNE: usize = 564;
-pub const ID_DIAMOND_BOLT_TIPS: usize = 9192;
-pub const ID_DIAMOND_DRAGON_BOLT: usize = 21969;
-pub const ID_DIAMOND_DRAGON_BOLT_E: usize = 21946;
-pub const ID_DRAGONSTONE_BOLT_TIPS: usize = 9193;
-pub const ID_DRAGONSTONE_DRAGON_BOLT: usize = 9341;
-pub const ID_DRAGONSTONE_DRAGON_BOLT_E: usize = 21948;
-pub const ID_DRAGON_BOLT_UNF: usize = 21930;
-pub const ID_LAW_RUNE: usize = 563;
-pub const ID_MAHOGANY_LOG: usize = 6332;
-pub const ID_MAHOGANY_PLANK: usize = 8782;
-pub const ID_NATURE_RUNE: usize = 561;
-pub const ID_OPAL_BOLT_TIPS: usize = 45;
-pub const ID_OPAL_DRAGON_BOLT: usize = 21955;
-pub const ID_OPAL_DRAGON_BOLT_E: usize = 21932;
-pub const ID_RED_DRAGONHIDE: usize = 1749;
-pub const ID_RED_DRAGON_LEATHER: usize = 2507;
-pub const ID_RUBY_BOLT_TIPS: usize = 9191;
-pub const ID_RUBY_DRAGON_BOLT: usize = 21967;
-pub const ID_RUBY_DRAGON_BOLT_E: usize = 21944;
-pub const ID_SOFT_CLAY: usize = 434;
-pub const ID_SOUL_RUNE: usize = 566;
-pub const ID_TEAK_LOG: usize = 6333;
-pub const ID_TEAK_PLANK: usize = 8780;
@@ -25,6 +25,12 @@ pub struct ItemPrice {
     pub high_time: Option<usize>,
     pub low: Option<usize>,
     pub low_time: Option<usize>,
+    /// Number of items traded at the high price. Only known for averaged prices.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_volume: Option<usize>,
+    /// Number of items traded at the low price. Only known for averaged prices.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_volume: Option<usize>,
 }
 
 /// Response for the `/latest` endpoint of the price API
@@ -33,6 +39,37 @@ pub struct ItemPriceResponse {
     pub data: HashMap<usize, ItemPrice>,
 }
 
+/// Averaged price data for an in-game item over a time window
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AveragePrice {
+    pub avg_high_price: Option<usize>,
+    pub high_price_volume: usize,
+    pub avg_low_price: Option<usize>,
+    pub low_price_volume: usize,
+}
+
+impl AveragePrice {
+    /// Convert into an [`ItemPrice`] where both sides were seen at `timestamp`
+    pub fn into_item_price(self, timestamp: usize) -> ItemPrice {
+        ItemPrice {
+            high: self.avg_high_price,
+            high_time: self.avg_high_price.map(|_| timestamp),
+            low: self.avg_low_price,
+            low_time: self.avg_low_price.map(|_| timestamp),
+            high_volume: Some(self.high_price_volume),
+            low_volume: Some(self.low_price_volume),
+        }
+    }
+}
+
+/// Response for the `/5m` and `/1h` endpoints of the price API
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AveragePriceResponse {
+    pub timestamp: usize,
+    pub data: HashMap<usize, AveragePrice>,
+}
+
 /// Response for the `/volumes` endpoint of the price API
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DailyVolumeResponse {
@@ -40,6 +77,56 @@ pub struct DailyVolumeResponse {
     pub data: HashMap<usize, usize>,
 }
 
+/// Which of an item's prices to use
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSide {
+    /// Recent instant-sell price, what an item can be bought for with a buy offer
+    #[default]
+    Low,
+    /// Recent instant-buy price, what an item can be sold for with a sell offer
+    High,
+    /// Average of the low and high price
+    Avg,
+}
+
+impl std::fmt::Display for PriceSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriceSide::Low => write!(f, "low"),
+            PriceSide::High => write!(f, "high"),
+            PriceSide::Avg => write!(f, "average"),
+        }
+    }
+}
+
+/// Why the data a method needs for an item isn't available
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ItemError {
+    /// The id is not in the item mapping
+    UnknownItem { id: usize },
+    /// The item hasn't been traded recently on this side
+    MissingPrice { id: usize, side: PriceSide },
+    /// The item has no known buy limit
+    MissingLimit { id: usize },
+    /// The item is only known by a name that hasn't been resolved to an id
+    Unresolved { name: String },
+}
+
+impl std::fmt::Display for ItemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemError::UnknownItem { id } => write!(f, "item {id} is not in the item mapping"),
+            ItemError::MissingPrice { id, side } => write!(f, "item {id} has no {side} price"),
+            ItemError::MissingLimit { id } => write!(f, "item {id} has no buy limit"),
+            ItemError::Unresolved { name } => write!(f, "item {name:?} hasn't been resolved"),
+        }
+    }
+}
+
+impl std::error::Error for ItemError {}
+
 /// An item's core data paired with its current price data. Price data will be
 /// `None` if it hasn't been traded recently.
 #[derive(Clone, Debug)]
@@ -50,21 +137,61 @@ pub struct ItemWithPrice<'a> {
 }
 
 impl<'a> ItemWithPrice<'a> {
-    /// Get the average of the recent high and low prices.
-    pub fn avg(&self) -> usize {
-        let price = self.price.unwrap();
+    fn missing(&self, side: PriceSide) -> ItemError {
+        ItemError::MissingPrice {
+            id: self.item.id,
+            side,
+        }
+    }
+
+    /// Get the average of the recent high and low prices. Falls back to either
+    /// side if only one of them is known.
+    pub fn avg(&self) -> Result<usize, ItemError> {
+        let price = self.price.ok_or(self.missing(PriceSide::Avg))?;
         match (price.high, price.low) {
-            (Some(high), Some(low)) => (high + low) / 2,
-            (Some(value), None) | (None, Some(value)) => value,
-            (None, None) => unreachable!(),
+            (Some(high), Some(low)) => Ok((high + low) / 2),
+            (Some(value), None) | (None, Some(value)) => Ok(value),
+            (None, None) => Err(self.missing(PriceSide::Avg)),
         }
     }
 
-    pub fn high(&self) -> usize {
-        self.price.unwrap().high.unwrap()
+    pub fn high(&self) -> Result<usize, ItemError> {
+        self.price
+            .and_then(|p| p.high)
+            .ok_or(self.missing(PriceSide::High))
+    }
+
+    pub fn low(&self) -> Result<usize, ItemError> {
+        self.price
+            .and_then(|p| p.low)
+            .ok_or(self.missing(PriceSide::Low))
+    }
+
+    pub fn price(&self, side: PriceSide) -> Result<usize, ItemError> {
+        match side {
+            PriceSide::Low => self.low(),
+            PriceSide::High => self.high(),
+            PriceSide::Avg => self.avg(),
+        }
+    }
+
+    /// Unix timestamp of when the price of `side` was last traded. For the
+    /// average this is the older of the two sides.
+    pub fn time(&self, side: PriceSide) -> Option<usize> {
+        let price = self.price?;
+        match side {
+            PriceSide::Low => price.low_time,
+            PriceSide::High => price.high_time,
+            PriceSide::Avg => match (price.high_time, price.low_time) {
+                (Some(high), Some(low)) => Some(high.min(low)),
+                (high, low) => high.or(low),
+            },
+        }
     }
 
-    pub fn low(&self) -> usize {
-        self.price.unwrap().low.unwrap()
+    pub fn limit(&self) -> Result<usize, ItemError> {
+        self.item
+            .limit
+            .ok_or(ItemError::MissingLimit { id: self.item.id })
     }
 }
@@ -1,50 +1,177 @@
 use cache::Cache;
-use clap::{arg, Parser};
+use cli::{CacheCommand, Cli, Command, GlobalArgs, HistoryCommand, ReportArgs};
+use config::Config;
+use history::History;
+use http::Client;
+use makers::Report;
 use num_format::{Locale, ToFormattedString};
+use recipe::RecipeBook;
+use resolve::Resolver;
 
 mod cache;
-mod id;
+mod card;
+mod chain;
+mod cli;
+mod config;
+mod dataset;
+mod filter;
+mod flip;
+mod history;
+mod http;
 mod item;
 mod makers;
+mod output;
+mod plan;
+mod rank;
+mod recipe;
+mod resolve;
+mod tax;
+mod validate;
 
 pub fn fmt_int<T: ToFormattedString>(num: &T) -> String {
     let locale = Locale::en;
     num.to_formatted_string(&locale)
 }
 
-#[derive(Debug, Parser)]
-struct Cli {
-    /// query latest prices regardless of cache
-    #[arg(short, long, default_value_t = false)]
-    force: bool,
+/// Load the cache and the recipes, with every recipe item resolved and checked
+fn load(global: &GlobalArgs, client: &dyn Client) -> eyre::Result<(Cache, RecipeBook)> {
+    let mut recipes = global.recipes()?;
+    let cache = Cache::new(
+        &global.cache_dir()?,
+        client,
+        global.source,
+        &global.max_age(),
+    )?;
+    recipes.resolve(&Resolver::new(&cache.items))?;
+    validate::startup(&recipes, &cache.items)?;
+    Ok((cache, recipes))
+}
+
+/// Evaluate every recipe, then rank and filter the methods
+fn report(
+    global: &GlobalArgs,
+    client: &dyn Client,
+    mut config: Config,
+    args: &ReportArgs,
+) -> eyre::Result<(Cache, Report)> {
+    config.filter.overrides = args.filter();
+    let (mut cache, recipes) = load(global, client)?;
+    if let Some(timestamp) = args.at {
+        let history = History::new(&global.cache_dir()?, global.timestep);
+        cache.prices = history.prices_at(&makers::ids(&recipes), timestamp)?;
+    }
+
+    let mut report = makers::report(&cache, &recipes);
+    rank::sort(&mut report.entries, args.sort_by);
+    config.filter.apply(&mut report.entries);
+    Ok((cache, report))
 }
 
 fn main() -> eyre::Result<()> {
-    let cli = Cli::parse();
-    let cache = Cache::new(cli.force)?;
-    let mut report = makers::report(&cache);
-    report.sort();
-    report.reverse();
-
-    println!(
-        "{:>30} | {:>12} | {:>12} | {:>12} | {:>12} | {:>7}",
-        "name", "profit", "gross", "cost", "volume", "limit"
-    );
-
-    for r in report {
-        if r.profit.is_negative() || r.profit < 1_000_000 {
-            continue;
-        }
-
-        println!(
-            "{:>30} | {:>12} | {:>12} | {:>12} | {:>12} | {:>7}",
-            r.name,
-            fmt_int(&r.profit),
-            fmt_int(&r.gross),
-            fmt_int(&r.cost),
-            fmt_int(&r.volume),
-            fmt_int(&r.limit),
-        );
+    let cli = Cli::parse_args();
+    let global = &cli.global;
+    let config = Config::load(global.config.as_deref())?;
+    let client = global.client(&config.api);
+    let client = &*client;
+    let mut stdout = std::io::stdout().lock();
+
+    match cli.command() {
+        Command::Report(args) => {
+            let (_, report) = report(global, client, config, &args)?;
+            global
+                .format
+                .writer(global.source)
+                .write(&mut stdout, &report)?;
+        }
+        Command::Item { query } => {
+            let (cache, recipes) = load(global, client)?;
+            let id = Resolver::new(&cache.items).resolve(&query)?;
+            let card = card::card(&cache, &recipes, id)?;
+            output::write_item(&mut stdout, &card, global.format)?;
+        }
+        Command::History(HistoryCommand::Sync) => {
+            let (_, recipes) = load(global, client)?;
+            let added = History::new(&global.cache_dir()?, global.timestep)
+                .sync(client, &makers::ids(&recipes))?;
+            eprintln!(
+                "Added {} {} price points to history",
+                added,
+                global.timestep.as_str()
+            );
+        }
+        Command::History(HistoryCommand::Show { query }) => {
+            let items = cache::mapping(client.base_url(), global.max_age().mapping)
+                .load(&global.cache_dir()?, client)?;
+            let id = Resolver::new(&items).resolve(&query)?;
+            let points = History::new(&global.cache_dir()?, global.timestep).load(id)?;
+            output::write_history(&mut stdout, &items[&id], &points, global.format)?;
+        }
+        Command::Cache(command) => {
+            let dir = global.cache_dir()?;
+            let max_age = global.max_age();
+            match command {
+                CacheCommand::Status => {
+                    let status = cache::status(&dir, &cache::datasets(client.base_url(), &max_age));
+                    output::write_cache_status(&mut stdout, &status, global.format)?;
+                }
+                CacheCommand::Refresh { datasets } => {
+                    for dataset in cache::select(client.base_url(), &max_age, &datasets)? {
+                        let count = dataset.refresh(&dir, client)?;
+                        eprintln!("Fetched {} items of {}", fmt_int(&count), dataset.name());
+                    }
+                }
+                CacheCommand::Clear { datasets } => {
+                    for dataset in cache::select(client.base_url(), &max_age, &datasets)? {
+                        dataset.clear(&dir)?;
+                    }
+                }
+                CacheCommand::Export { file, datasets } => {
+                    let datasets = cache::select(client.base_url(), &max_age, &datasets)?;
+                    let exported = cache::export(&dir, &datasets, &file)?;
+                    eprintln!("Exported {} to {}", exported.join(", "), file.display());
+                }
+                CacheCommand::Import { file } => {
+                    let imported =
+                        cache::import(&dir, &cache::datasets(client.base_url(), &max_age), &file)?;
+                    eprintln!("Imported {} from {}", imported.join(", "), file.display());
+                }
+            }
+        }
+        Command::Plan { gp, report: args } => {
+            let (cache, report) = report(global, client, config, &args)?;
+            let plan = plan::plan(&cache, &report.entries, gp);
+            output::write_plan(&mut stdout, &plan, global.format)?;
+        }
+        Command::Chains => {
+            let (cache, recipes) = load(global, client)?;
+            let chains = chain::report(&cache, &recipes);
+            output::write_chains(&mut stdout, &chains, global.format)?;
+        }
+        Command::Flip(args) => {
+            let cache = Cache::new(
+                &global.cache_dir()?,
+                client,
+                global.source,
+                &global.max_age(),
+            )?;
+            let mut flips = flip::scan(&cache, &args.criteria(global.source))?;
+            flips.truncate(args.top);
+            output::write_flips(&mut stdout, &flips, global.format)?;
+        }
+        Command::Recipes => {
+            let (cache, recipes) = load(global, client)?;
+            output::write_recipes(&mut stdout, &cache, &recipes, global.format)?;
+        }
+        Command::Validate { mapping } => {
+            let mut recipes = global.recipes()?;
+            let items = match mapping {
+                Some(path) => validate::load_mapping(&path)?,
+                None => cache::mapping(client.base_url(), global.max_age().mapping)
+                    .load(&global.cache_dir()?, client)?,
+            };
+            recipes.resolve(&Resolver::new(&items))?;
+            validate::run(&mut stdout, &recipes, &items)?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,126 @@
+//! Buying items on the GE to cast High Level Alchemy on them.
+//!
+//! Every item in the mapping with a high alch value is considered, so unlike
+//! recipes there is nothing to configure. Items without a price or a buy limit
+//! can't be bought in bulk and are left out of the report rather than skipped.
+//! With `report --at` that is every item not used by a recipe, as only the
+//! items of recipes have their price history synced.
+
+use super::{Line, Maker, Report, ReportEntry, Skipped, ID_COINS};
+use crate::{cache::Cache, item::PriceSide};
+
+pub const ID_NATURE_RUNE: usize = 561;
+
+/// Casts of High Level Alchemy in an hour, at one every 3 seconds
+pub const ALCHS_PER_HOUR: usize = 1200;
+
+pub struct HighAlch;
+
+impl Maker for HighAlch {
+    fn run(&self, cache: &Cache) -> Report {
+        let mut report = Report::default();
+        let nature_rune = match cache
+            .get(ID_NATURE_RUNE)
+            .and_then(|rune| Line::new(&rune, PriceSide::Avg, 1))
+        {
+            Ok(line) => line,
+            Err(reason) => {
+                report.skipped.push(Skipped {
+                    name: "High alchemy".to_string(),
+                    reason,
+                });
+                return report;
+            }
+        };
+
+        let mut items: Vec<_> = cache.items.values().collect();
+        items.sort_by_key(|item| item.id);
+        for item in items {
+            let Some(high_alch) = item.high_alch.filter(|_| item.id != ID_COINS) else {
+                continue;
+            };
+            let Ok(item) = cache.get(item.id) else {
+                continue;
+            };
+            let (Ok(limit), Ok(input)) = (item.limit(), Line::new(&item, PriceSide::Low, 1)) else {
+                continue;
+            };
+
+            let inputs = vec![
+                Line {
+                    quantity: limit,
+                    ..input
+                },
+                Line {
+                    quantity: limit,
+                    ..nature_rune.clone()
+                },
+            ];
+            // Coins are sold for 1gp each, which is too little to be taxed
+            let outputs = vec![Line::coins(high_alch * limit)];
+            report.entries.push(ReportEntry {
+                actions_per_hour: Some(ALCHS_PER_HOUR),
+                ..ReportEntry::new(
+                    &format!("High alch {}", item.item.name),
+                    "alching",
+                    inputs,
+                    outputs,
+                    limit,
+                    item.volume,
+                    limit,
+                )
+            });
+        }
+        report
+    }
+
+    /// Only the nature rune. Syncing the history of every item that can be
+    /// alched would take thousands of requests, so only those used by recipes
+    /// have a price with `report --at`.
+    fn ids(&self) -> Vec<usize> {
+        vec![ID_NATURE_RUNE]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLATEBODY: usize = 1127;
+
+    fn cache() -> Cache {
+        let mut cache = Cache::default()
+            .with_item(ID_NATURE_RUNE, "Nature rune", 18_000, (100, 110), 1_000_000)
+            .with_item(PLATEBODY, "Rune platebody", 70, (38_000, 38_500), 20_000)
+            .with_item(ID_COINS, "Coins", 1, (1, 1), 0);
+        cache.items.get_mut(&PLATEBODY).unwrap().high_alch = Some(39_000);
+        cache.items.get_mut(&ID_COINS).unwrap().high_alch = Some(1);
+        cache
+    }
+
+    #[test]
+    fn alchs_whole_limit() {
+        let report = HighAlch.run(&cache());
+
+        assert!(report.skipped.is_empty());
+        let [entry] = &report.entries[..] else {
+            panic!("expected only the platebody: {:?}", report.entries);
+        };
+        assert_eq!(entry.name, "High alch Rune platebody");
+        assert_eq!(entry.actions, 70);
+        // The platebody at its low price and the rune at its average
+        assert_eq!(entry.cost, 70 * (38_000 + 105));
+        assert_eq!(entry.gross, 70 * 39_000);
+        assert_eq!(entry.tax, 0);
+    }
+
+    #[test]
+    fn skipped_without_nature_runes() {
+        let mut cache = cache();
+        cache.prices.remove(&ID_NATURE_RUNE);
+        let report = HighAlch.run(&cache);
+
+        assert!(report.entries.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+    }
+}
@@ -1,51 +1,265 @@
-use super::{Maker, ReportEntry};
-
-pub struct Decanting;
-
-/// Potion 3 id, Potion 4 id
-#[rustfmt::skip]
-const POTION_IDS: &[(usize, usize)] = &[
-    (3034,  3032),  (2454,  2452),
-    (121,   2428),  (22464, 22461),
-    (22452, 22449), (9741,  9739),
-    (6472,  6470),  (133,   2432),
-    (24638, 24635), (24626, 24623),
-    (23748, 23745), (23736, 23733),
-    (23700, 23697), (23688, 23685),
-    (23724, 23721), (23712, 23709),
-    (3010,  3008),  (151,   2438),
-    (10000, 9998),  (3042,  3040),
-    (139,   2434),  (169,   2444),
-    (127,   2430),  (12627, 12625),
-    (115,   113),   (21981, 21978),
-    (12697, 12695),
-];
-
-impl Maker for Decanting {
-    fn run(cache: &crate::cache::Cache) -> super::Report {
-        let mut report = Vec::new();
-
-        for (id_3, id_4) in POTION_IDS {
-            let item_3 = cache.get(*id_3);
-            let item_4 = cache.get(*id_4);
-
-            let limit = item_3.item.limit.unwrap();
-            let decant_total = (limit * 3) / 4;
-            let cost = item_3.low() * limit;
-            let gross = item_4.high() * decant_total;
-
-            // Only care about potions that will have some movement on the ge
-            if item_4.volume > 100_000 {
-                report.push(ReportEntry::new(
-                    item_3.item.name.as_str(),
-                    gross,
-                    cost,
-                    limit,
-                    item_4.volume,
-                ));
+//! Decanting potions between any of their doses.
+//!
+//! Bob Barter decants for free, so what matters is the price of a single dose.
+//! Every dose of a potion is tried as the one being sold, buying every other
+//! dose that is cheaper per dose for it, cheapest first, and the most
+//! profitable of them is kept. How much of each dose can be bought is capped by
+//! its buy limit and by how much of it trades within a limit window, and no
+//! more is bought than the dose being sold can be expected to move in that
+//! window, so a dose that pays the most per dose but hardly trades can lose to
+//! a more liquid one.
+
+use super::{Line, Maker, Report, ReportEntry, Skipped};
+use crate::{
+    cache::Cache,
+    item::{ItemError, ItemWithPrice, PriceSide},
+    rank::window_volume,
+    recipe::Potion,
+    tax,
+};
+
+/// Potions decanted in an hour, including banking
+pub const DECANTS_PER_HOUR: usize = 10_000;
+
+pub struct Decanting<'a>(pub &'a [Potion]);
+
+impl Maker for Decanting<'_> {
+    fn run(&self, cache: &Cache) -> Report {
+        let mut report = Report::default();
+        for potion in self.0 {
+            match evaluate(cache, potion) {
+                Ok(entry) => report.entries.push(entry),
+                Err(reason) => report.skipped.push(Skipped {
+                    name: potion.name.clone(),
+                    reason,
+                }),
             }
         }
-
         report
     }
+
+    fn ids(&self) -> Vec<usize> {
+        self.0.iter().flat_map(Potion::ids).collect()
+    }
+}
+
+/// A dose variant with its prices per dose
+struct Variant<'a> {
+    doses: usize,
+    item: ItemWithPrice<'a>,
+    /// Price of a single dose when buying
+    buy: Result<f64, ItemError>,
+    /// Price of a single dose when selling, after tax
+    sell: Result<f64, ItemError>,
+}
+
+/// Lines selling `doses` of `potion` decanted into its `into`-dose form at
+/// `side`. Doses that don't make up a whole potion are sold as a single smaller
+/// one, so a leftover dose without a price fails the whole sale.
+pub fn sell_decanted(
+    cache: &Cache,
+    potion: &Potion,
+    doses: usize,
+    into: usize,
+    side: PriceSide,
+) -> Result<Vec<Line>, ItemError> {
+    let dose = |doses: usize| cache.get(potion.doses[doses - 1].item.id()?);
+    let mut lines = vec![Line::new(&dose(into)?, side, doses / into)?];
+    let leftover = doses % into;
+    if leftover > 0 {
+        lines.push(Line::new(&dose(leftover)?, side, 1)?);
+    }
+    Ok(lines)
+}
+
+/// Work out the most profitable way of decanting `potion` within a limit window
+pub fn evaluate(cache: &Cache, potion: &Potion) -> Result<ReportEntry, ItemError> {
+    let mut variants = Vec::new();
+    for (i, dose) in potion.doses.iter().enumerate() {
+        let item = cache.get(dose.item.id()?)?;
+        // Doses are listed from 1 up, as checked by `validate`
+        let doses = i + 1;
+        let buy = item
+            .price(PriceSide::Low)
+            .map(|low| low as f64 / doses as f64);
+        let sell = item
+            .price(PriceSide::High)
+            .map(|high| (high - tax::per_item(item.item.id, high)) as f64 / doses as f64);
+        variants.push(Variant {
+            doses,
+            item,
+            buy,
+            sell,
+        });
+    }
+
+    let mut sellable = variants
+        .iter()
+        .filter_map(|v| v.sell.as_ref().ok().map(|sell| (v, *sell)))
+        .collect::<Vec<_>>();
+    if sellable.is_empty() {
+        return Err(ItemError::MissingPrice {
+            id: variants[variants.len() - 1].item.item.id,
+            side: PriceSide::High,
+        });
+    }
+    // Best paying dose first, so its error is the one given when none of them
+    // can be decanted into
+    sellable.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let mut best: Option<ReportEntry> = None;
+    let mut failed = None;
+    for (sold, sell) in sellable {
+        match decant_into(cache, potion, &variants, sold, sell) {
+            Ok(entry) if best.as_ref().is_none_or(|b| entry.profit > b.profit) => {
+                best = Some(entry)
+            }
+            Ok(_) => {}
+            Err(err) => failed = failed.or(Some(err)),
+        }
+    }
+    best.ok_or_else(|| failed.expect("at least one dose is sellable"))
+}
+
+/// Work out the profit of buying the other doses of `potion` to sell them as
+/// `sold`, which sells for `sell` per dose
+fn decant_into(
+    cache: &Cache,
+    potion: &Potion,
+    variants: &[Variant],
+    sold: &Variant,
+    sell: f64,
+) -> Result<ReportEntry, ItemError> {
+    let mut bought = Vec::new();
+    let mut missing = None;
+    for variant in variants.iter().filter(|v| v.doses != sold.doses) {
+        match variant
+            .buy
+            .clone()
+            .and_then(|buy| Ok((buy, variant.item.limit()?)))
+        {
+            Ok((buy, limit)) => bought.push((variant, buy, limit)),
+            Err(err) => missing = missing.or(Some(err)),
+        }
+    }
+    if let (true, Some(err)) = (bought.is_empty(), missing) {
+        return Err(err);
+    }
+    bought.sort_by(|(_, a, _), (_, b, _)| a.total_cmp(b));
+    // Only the cheapest dose when none of them make a profit, to show by how
+    // much decanting falls short
+    let profitable = bought.iter().filter(|(_, buy, _)| *buy < sell).count();
+    bought.truncate(profitable.max(1));
+
+    let mut doses_left = window_volume(sold.item.volume) * sold.doses;
+    let mut inputs = Vec::new();
+    let mut doses = 0;
+    for (variant, _, limit) in bought {
+        let tradeable = limit.min(window_volume(variant.item.volume));
+        let quantity = tradeable.min(doses_left / variant.doses);
+        if quantity == 0 {
+            continue;
+        }
+        doses_left -= quantity * variant.doses;
+        doses += quantity * variant.doses;
+        inputs.push(Line::new(&variant.item, PriceSide::Low, quantity)?);
+    }
+
+    let outputs = sell_decanted(cache, potion, doses, sold.doses, PriceSide::High)?;
+
+    let mut bought_doses = variants
+        .iter()
+        .filter(|v| inputs.iter().any(|i| i.id == v.item.item.id))
+        .map(|v| v.doses.to_string())
+        .collect::<Vec<_>>();
+    bought_doses.sort();
+    let name = format!(
+        "{}({}) to ({})",
+        potion.name,
+        bought_doses.join("+"),
+        sold.doses
+    );
+    let potions = outputs[0].quantity;
+    Ok(ReportEntry {
+        actions_per_hour: Some(DECANTS_PER_HOUR),
+        ..ReportEntry::new(
+            &name,
+            "decanting",
+            inputs,
+            outputs,
+            potions,
+            sold.item.volume,
+            potions,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipe::RecipeBook;
+
+    fn potion() -> Potion {
+        let book = RecipeBook::from_toml(
+            r#"
+            [[potion]]
+            name = "Attack potion"
+            doses = [{ item = 125 }, { item = 123 }, { item = 121 }, { item = 2428 }]
+            "#,
+        )
+        .unwrap();
+        book.potions[0].clone()
+    }
+
+    fn cache() -> Cache {
+        Cache::default()
+            .with_item(125, "Attack potion(1)", 1_000, (300, 310), 60_000)
+            .with_item(123, "Attack potion(2)", 1_000, (500, 510), 60_000)
+            .with_item(121, "Attack potion(3)", 1_000, (900, 910), 60_000)
+            .with_item(2428, "Attack potion(4)", 1_000, (1_400, 1_410), 60_000)
+    }
+
+    #[test]
+    fn sells_leftover_doses() {
+        let lines = sell_decanted(&cache(), &potion(), 10, 4, PriceSide::High).unwrap();
+        let sold: Vec<_> = lines.iter().map(|l| (l.id, l.quantity)).collect();
+        assert_eq!(sold, [(2428, 2), (123, 1)]);
+    }
+
+    #[test]
+    fn skipped_when_leftover_dose_has_no_price() {
+        let mut cache = cache();
+        cache.prices.remove(&123);
+        assert_eq!(
+            sell_decanted(&cache, &potion(), 10, 4, PriceSide::High).unwrap_err(),
+            ItemError::MissingPrice {
+                id: 123,
+                side: PriceSide::High
+            }
+        );
+
+        // 1,002 of (1) and 1,000 of (3) leave 2 doses over after decanting
+        // them into (4), the only dose that can be sold
+        cache.items.get_mut(&125).unwrap().limit = Some(1_002);
+        cache.prices.get_mut(&125).unwrap().high = None;
+        cache.prices.get_mut(&121).unwrap().high = None;
+        let report = Decanting(&[potion()]).run(&cache);
+        assert!(report.entries.is_empty());
+        assert_eq!(report.skipped[0].name, "Attack potion");
+    }
+
+    #[test]
+    fn sells_the_dose_that_makes_the_most() {
+        // (1) and (2) pay the most per dose but hardly trade, (4) trades plenty
+        // and (3) is the only dose cheap enough to buy for it
+        let cache = cache()
+            .with_item(125, "Attack potion(1)", 1_000, (420, 430), 600)
+            .with_item(123, "Attack potion(2)", 1_000, (1_000, 1_010), 600)
+            .with_item(2428, "Attack potion(4)", 1_000, (1_500, 1_520), 600_000);
+        let entry = evaluate(&cache, &potion()).unwrap();
+        assert_eq!(entry.name, "Attack potion(3) to (4)");
+        assert_eq!(entry.outputs[0].id, 2428);
+        assert_eq!(entry.outputs[0].quantity, 750);
+        assert_eq!(entry.profit, 750 * (1_520 - 30) - 1_000 * 900);
+    }
 }
@@ -1,42 +1,135 @@
-use std::vec;
+use serde::Serialize;
 
-use crate::cache::Cache;
+use self::{alch::HighAlch, decanting::Decanting};
+use crate::{
+    cache::Cache,
+    item::{ItemError, ItemWithPrice, PriceSide},
+    recipe::RecipeBook,
+    tax,
+};
 
-mod bolts;
-mod clay;
-mod cooking;
-mod decanting;
-mod herbs;
-mod leather;
-mod planks;
+pub mod alch;
+pub mod decanting;
+pub mod recipes;
 
-pub type Report = Vec<ReportEntry>;
+/// The methods a maker could evaluate and the ones it had to skip
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub entries: Vec<ReportEntry>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// A method that could not be evaluated
+#[derive(Debug, Serialize)]
+pub struct Skipped {
+    pub name: String,
+    pub reason: ItemError,
+}
+
+/// Some quantity of an item bought or sold by a method for `price` each
+#[derive(Clone, Debug, Serialize)]
+pub struct Line {
+    pub id: usize,
+    pub name: String,
+    pub quantity: usize,
+    pub price: usize,
+    /// Which of the item's prices was used
+    pub side: PriceSide,
+    /// Unix timestamp of when the price was last traded, if known
+    pub price_time: Option<usize>,
+}
+
+impl Line {
+    pub fn new(item: &ItemWithPrice, side: PriceSide, quantity: usize) -> Result<Self, ItemError> {
+        Ok(Self {
+            id: item.item.id,
+            name: item.item.name.clone(),
+            quantity,
+            price: item.price(side)?,
+            side,
+            price_time: item.time(side),
+        })
+    }
+
+    /// Coins paid straight to an NPC, e.g. a sawmill fee, or received from a
+    /// spell
+    pub fn coins(quantity: usize) -> Self {
+        Self {
+            id: ID_COINS,
+            name: "Coins".to_string(),
+            quantity,
+            price: 1,
+            side: PriceSide::Avg,
+            price_time: None,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.price * self.quantity
+    }
+}
 
-#[derive(Debug)]
+pub const ID_COINS: usize = 995;
+
+#[derive(Clone, Debug, Serialize)]
 pub struct ReportEntry {
     pub name: String,
+    pub category: String,
+    /// Everything bought to use the whole limit, including runes and fees
+    pub inputs: Vec<Line>,
+    /// Everything sold after using the whole limit
+    pub outputs: Vec<Line>,
+    /// What the outputs sell for on the GE
+    pub gross_before_tax: usize,
+    /// GE tax paid on the outputs
+    pub tax: usize,
+    /// What the outputs sell for after tax
     pub gross: usize,
     pub cost: usize,
     pub limit: usize,
     pub profit: isize,
     pub volume: usize,
-    // pub rank: f32,
+    /// Number of actions needed to use the whole limit
+    pub actions: usize,
+    /// How many actions can be done in an hour, if known
+    pub actions_per_hour: Option<usize>,
 }
 
 impl ReportEntry {
-    pub fn new(name: &str, gross: usize, cost: usize, limit: usize, volume: usize) -> Self {
+    /// Create an entry for a method that buys the `inputs` and sells the
+    /// `outputs` over `actions` actions. The pace of the method is unknown.
+    pub fn new(
+        name: &str,
+        category: &str,
+        inputs: Vec<Line>,
+        outputs: Vec<Line>,
+        limit: usize,
+        volume: usize,
+        actions: usize,
+    ) -> Self {
+        let cost = inputs.iter().map(Line::total).sum::<usize>();
+        let gross_before_tax = outputs.iter().map(Line::total).sum::<usize>();
+        let tax = outputs
+            .iter()
+            .map(|o| tax::total(o.id, o.price, o.quantity))
+            .sum::<usize>();
+        let gross = gross_before_tax - tax;
         let profit = gross as isize - cost as isize;
-        // TODO: weight methods that dont have high volume lower even if the profit high
-        // let rank = ((limit as f32 / volume as f32) * 1.0) * profit as f32; //  * 1.0;
 
         Self {
             profit,
             name: name.to_string(),
+            category: category.to_string(),
+            inputs,
+            outputs,
+            gross_before_tax,
+            tax,
             gross,
             cost,
             limit,
             volume,
-            // rank,
+            actions,
+            actions_per_hour: None,
         }
     }
 }
@@ -51,7 +144,7 @@ impl Eq for ReportEntry {}
 
 impl PartialOrd for ReportEntry {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.profit.partial_cmp(&other.profit)
+        Some(self.cmp(other))
     }
 }
 
@@ -62,20 +155,45 @@ impl PartialEq for ReportEntry {
 }
 
 pub trait Maker {
-    fn run(cache: &Cache) -> Report;
+    fn run(&self, cache: &Cache) -> Report;
+
+    /// Ids of every item the maker looks up
+    fn ids(&self) -> Vec<usize>;
+}
+
+impl<M: Maker + ?Sized> Maker for &M {
+    fn run(&self, cache: &Cache) -> Report {
+        (**self).run(cache)
+    }
+
+    fn ids(&self) -> Vec<usize> {
+        (**self).ids()
+    }
 }
 
-pub fn report(cache: &Cache) -> Report {
-    vec![
-        bolts::Bolts::run(cache),
-        clay::Clay::run(cache),
-        cooking::Cooking::run(cache),
-        decanting::Decanting::run(cache),
-        herbs::Herbs::run(cache),
-        leather::Leather::run(cache),
-        planks::Plank::run(cache),
+/// Every maker, with the recipes first
+fn makers(recipes: &RecipeBook) -> [Box<dyn Maker + '_>; 3] {
+    [
+        Box::new(recipes),
+        Box::new(Decanting(&recipes.potions)),
+        Box::new(HighAlch),
     ]
-    .into_iter()
-    .flatten()
-    .collect()
+}
+
+pub fn report(cache: &Cache, recipes: &RecipeBook) -> Report {
+    let mut report = Report::default();
+    for maker in makers(recipes) {
+        let Report { entries, skipped } = maker.run(cache);
+        report.entries.extend(entries);
+        report.skipped.extend(skipped);
+    }
+    report
+}
+
+/// Ids of every item used by any maker
+pub fn ids(recipes: &RecipeBook) -> Vec<usize> {
+    let mut ids: Vec<usize> = makers(recipes).iter().flat_map(|m| m.ids()).collect();
+    ids.sort();
+    ids.dedup();
+    ids
 }
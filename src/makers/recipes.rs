@@ -0,0 +1,203 @@
+use super::{decanting, Line, Maker, Report, ReportEntry, Skipped};
+use crate::{
+    cache::Cache,
+    item::{ItemError, PriceSide},
+    recipe::{ItemRef, Potion, Recipe, RecipeBook},
+};
+
+impl Maker for RecipeBook {
+    fn run(&self, cache: &Cache) -> Report {
+        let mut report = Report::default();
+        for recipe in &self.recipes {
+            match evaluate(cache, recipe, &self.potions) {
+                Ok(entry) => report.entries.push(entry),
+                Err(reason) => report.skipped.push(Skipped {
+                    name: name(cache, recipe),
+                    reason,
+                }),
+            }
+        }
+        report
+    }
+
+    fn ids(&self) -> Vec<usize> {
+        self.recipes.iter().flat_map(Recipe::ids).collect()
+    }
+}
+
+/// Name of the recipe with the `{input}` and `{output}` placeholders filled in.
+/// Items missing from the mapping are named by their id.
+pub fn name(cache: &Cache, recipe: &Recipe) -> String {
+    named(cache, recipe, &recipe.outputs[0].item)
+}
+
+/// Name of `item` in the mapping, its id when it's missing from the mapping or
+/// the name it was given when it hasn't been resolved
+pub fn item_name(cache: &Cache, item: &ItemRef) -> String {
+    match item {
+        ItemRef::Id(id) => cache
+            .get(*id)
+            .map(|item| item.item.name.clone())
+            .unwrap_or_else(|_| format!("#{id}")),
+        ItemRef::Name(name) => name.clone(),
+    }
+}
+
+/// Name of the recipe when `output` is sold instead of its first output
+fn named(cache: &Cache, recipe: &Recipe, output: &ItemRef) -> String {
+    let template = recipe.name.as_deref().unwrap_or("{output}");
+    template
+        .replace("{input}", &item_name(cache, &recipe.inputs[0].item))
+        .replace("{output}", &item_name(cache, output))
+}
+
+/// Work out the profit of doing as many actions of `recipe` as its limit
+/// allows. A potion that can be decanted is looked up among `potions`.
+pub fn evaluate(
+    cache: &Cache,
+    recipe: &Recipe,
+    potions: &[Potion],
+) -> Result<ReportEntry, ItemError> {
+    let limit = match recipe.limit {
+        Some(limit) => limit,
+        None => cache.get(recipe.limiting().id()?)?.limit()?,
+    };
+    let actions = limit / recipe.limiting_quantity();
+
+    let mut inputs = Vec::new();
+    for input in &recipe.inputs {
+        let item = cache.get(input.item.id()?)?;
+        inputs.push(Line::new(&item, input.price, input.quantity * actions)?);
+    }
+    for rune in &recipe.runes {
+        let item = cache.get(rune.item.id()?)?;
+        inputs.push(Line::new(&item, PriceSide::Avg, rune.quantity * actions)?);
+    }
+    if recipe.fee > 0 {
+        inputs.push(Line::coins(recipe.fee * actions));
+    }
+
+    let mut outputs = Vec::new();
+    for output in &recipe.outputs {
+        let item = cache.get(output.item.id()?)?;
+        outputs.push(Line::new(&item, output.price, output.quantity * actions)?);
+    }
+
+    let entry = |outputs: Vec<Line>| -> Result<ReportEntry, ItemError> {
+        let sold = outputs[0].id;
+        Ok(ReportEntry {
+            actions_per_hour: recipe.actions_per_hour,
+            ..ReportEntry::new(
+                &named(cache, recipe, &ItemRef::Id(sold)),
+                &recipe.category,
+                inputs.clone(),
+                outputs,
+                limit,
+                cache.get(sold)?.volume,
+                actions,
+            )
+        })
+    };
+
+    let Some(decant) = &recipe.decant else {
+        return entry(outputs);
+    };
+    // The potion is sold as made whenever its decanted form can't be priced
+    let made = &recipe.outputs[0];
+    let decanted = potions
+        .iter()
+        .find_map(|potion| Some((potion, potion.dose(&made.item)?, potion.dose(&decant.item)?)))
+        .and_then(|(potion, doses, into)| {
+            let doses = outputs[0].quantity * doses;
+            let mut decanted =
+                decanting::sell_decanted(cache, potion, doses, into, made.price).ok()?;
+            decanted.extend(outputs[1..].iter().cloned());
+            entry(decanted).ok()
+        });
+    let undecanted = entry(outputs)?;
+    Ok(match decanted {
+        Some(decanted) if decanted.profit > undecanted.profit => decanted,
+        _ => undecanted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Six attack potions, decantable into four 4-dose potions and a 2-dose one
+    fn book() -> RecipeBook {
+        RecipeBook::from_toml(
+            r#"
+            [[recipe]]
+            category = "potions"
+            inputs = [{ item = 91 }]
+            outputs = [{ item = 121 }]
+            decant = { item = 2428 }
+            limit = 6
+
+            [[potion]]
+            name = "Attack potion"
+            doses = [{ item = 125 }, { item = 123 }, { item = 121 }, { item = 2428 }]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn cache() -> Cache {
+        [
+            (91, "Guam potion (unf)", 100),
+            (125, "Attack potion(1)", 350),
+            (123, "Attack potion(2)", 700),
+            (121, "Attack potion(3)", 1_000),
+            (2428, "Attack potion(4)", 1_400),
+        ]
+        .into_iter()
+        .fold(Cache::default(), |cache, (id, name, price)| {
+            cache.with_item(id, name, 1_000, (price, price), 10_000)
+        })
+    }
+
+    fn sold(entry: &ReportEntry) -> Vec<(&str, usize)> {
+        entry
+            .outputs
+            .iter()
+            .map(|line| (line.name.as_str(), line.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn decants_leftover_doses_into_smaller_potion() {
+        let book = book();
+        let entry = evaluate(&cache(), &book.recipes[0], &book.potions).unwrap();
+
+        assert_eq!(entry.name, "Attack potion(4)");
+        assert_eq!(
+            sold(&entry),
+            [("Attack potion(4)", 4), ("Attack potion(2)", 1)]
+        );
+        assert_eq!(entry.gross, 4 * (1_400 - 28) + (700 - 14));
+    }
+
+    #[test]
+    fn sold_as_made_when_decanted_form_has_no_price() {
+        let book = book();
+        for missing in [2428, 123] {
+            let mut cache = cache();
+            cache.prices.remove(&missing);
+            let entry = evaluate(&cache, &book.recipes[0], &book.potions).unwrap();
+
+            assert_eq!(sold(&entry), [("Attack potion(3)", 6)], "{missing}");
+        }
+    }
+
+    #[test]
+    fn sold_as_made_when_it_pays_more() {
+        let book = book();
+        let mut cache = cache();
+        cache.prices.get_mut(&2428).unwrap().high = Some(1_200);
+        let entry = evaluate(&cache, &book.recipes[0], &book.potions).unwrap();
+
+        assert_eq!(sold(&entry), [("Attack potion(3)", 6)]);
+    }
+}
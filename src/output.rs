@@ -0,0 +1,580 @@
+//! Rendering of a [`Report`] in the supported output formats.
+
+use std::{
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use eyre::Result;
+use serde::Serialize;
+
+use crate::{
+    cache::{Cache, PriceSource, Status},
+    card::Card,
+    chain::ChainReport,
+    flip::Flip,
+    fmt_int,
+    history::PricePoint,
+    item::Item,
+    makers::{
+        recipes::{item_name, name},
+        Line, Report, ReportEntry, Skipped,
+    },
+    plan::Plan,
+    recipe::{ItemRef, RecipeBook},
+};
+
+/// Version of the JSON document written by [`Json`]. Bumped whenever a field is
+/// removed or changes meaning.
+pub const SCHEMA_VERSION: usize = 1;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Fixed width table for the terminal
+    #[default]
+    Table,
+    /// A single JSON document
+    Json,
+    /// One row per method
+    Csv,
+    /// GitHub flavoured markdown table
+    Markdown,
+}
+
+impl Format {
+    pub fn writer(&self, source: PriceSource) -> Box<dyn ReportWriter> {
+        match self {
+            Format::Table => Box::new(Table),
+            Format::Json => Box::new(Json { source }),
+            Format::Csv => Box::new(Csv),
+            Format::Markdown => Box::new(Markdown),
+        }
+    }
+}
+
+pub trait ReportWriter {
+    fn write(&self, out: &mut dyn Write, report: &Report) -> Result<()>;
+}
+
+fn gp_hour(entry: &ReportEntry) -> String {
+    entry
+        .gp_hour()
+        .map(|gp| fmt_int(&(gp as isize)))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// `3000 x Teak logs; 6000 x Astral rune`
+fn lines(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .map(|l| format!("{} x {}", l.quantity, l.name))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub struct Table;
+
+impl ReportWriter for Table {
+    fn write(&self, out: &mut dyn Write, report: &Report) -> Result<()> {
+        writeln!(
+            out,
+            "{:>30} | {:>12} | {:>12} | {:>10} | {:>12} | {:>12} | {:>12} | {:>7} | {:>8} | {:>10} | {:>12}",
+            "name", "profit", "pre-tax", "tax", "gross", "cost", "volume", "limit", "roi", "gp/hr", "gp/4h"
+        )?;
+
+        for r in &report.entries {
+            writeln!(
+                out,
+                "{:>30} | {:>12} | {:>12} | {:>10} | {:>12} | {:>12} | {:>12} | {:>7} | {:>7.1}% | {:>10} | {:>12}",
+                r.name,
+                fmt_int(&r.profit),
+                fmt_int(&r.gross_before_tax),
+                fmt_int(&r.tax),
+                fmt_int(&r.gross),
+                fmt_int(&r.cost),
+                fmt_int(&r.volume),
+                fmt_int(&r.limit),
+                r.roi() * 100.0,
+                gp_hour(r),
+                fmt_int(&(r.realisable() as isize)),
+            )?;
+        }
+
+        if !report.skipped.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "Skipped methods:")?;
+            for s in &report.skipped {
+                writeln!(out, "{:>30} | {}", s.name, s.reason)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Markdown;
+
+impl ReportWriter for Markdown {
+    fn write(&self, out: &mut dyn Write, report: &Report) -> Result<()> {
+        writeln!(
+            out,
+            "| name | profit | pre-tax | tax | gross | cost | volume | limit | roi | gp/hr | gp/4h |"
+        )?;
+        writeln!(
+            out,
+            "| --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: |"
+        )?;
+        for r in &report.entries {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {:.1}% | {} | {} |",
+                r.name.replace('|', "\\|"),
+                fmt_int(&r.profit),
+                fmt_int(&r.gross_before_tax),
+                fmt_int(&r.tax),
+                fmt_int(&r.gross),
+                fmt_int(&r.cost),
+                fmt_int(&r.volume),
+                fmt_int(&r.limit),
+                r.roi() * 100.0,
+                gp_hour(r),
+                fmt_int(&(r.realisable() as isize)),
+            )?;
+        }
+
+        if !report.skipped.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "### Skipped methods")?;
+            writeln!(out)?;
+            for s in &report.skipped {
+                writeln!(out, "- {}: {}", s.name, s.reason)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Csv;
+
+impl Csv {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+impl ReportWriter for Csv {
+    fn write(&self, out: &mut dyn Write, report: &Report) -> Result<()> {
+        writeln!(
+            out,
+            "name,category,profit,gross_before_tax,tax,gross,cost,volume,limit,actions,roi,gp_hour,realisable,inputs,outputs"
+        )?;
+        for r in &report.entries {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{:.4},{},{:.0},{},{}",
+                Csv::field(&r.name),
+                Csv::field(&r.category),
+                r.profit,
+                r.gross_before_tax,
+                r.tax,
+                r.gross,
+                r.cost,
+                r.volume,
+                r.limit,
+                r.actions,
+                r.roi(),
+                r.gp_hour().map(|gp| format!("{gp:.0}")).unwrap_or_default(),
+                r.realisable(),
+                Csv::field(&lines(&r.inputs)),
+                Csv::field(&lines(&r.outputs)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Json {
+    source: PriceSource,
+}
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a ReportEntry,
+    roi: f64,
+    gp_hour: Option<f64>,
+    realisable: f64,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: usize,
+    generated_at: u64,
+    price_source: PriceSource,
+    entries: Vec<JsonEntry<'a>>,
+    skipped: &'a [Skipped],
+}
+
+impl ReportWriter for Json {
+    fn write(&self, out: &mut dyn Write, report: &Report) -> Result<()> {
+        let document = JsonReport {
+            schema_version: SCHEMA_VERSION,
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            price_source: self.source,
+            entries: report
+                .entries
+                .iter()
+                .map(|entry| JsonEntry {
+                    entry,
+                    roi: entry.roi(),
+                    gp_hour: entry.gp_hour(),
+                    realisable: entry.realisable(),
+                })
+                .collect(),
+            skipped: &report.skipped,
+        };
+        serde_json::to_writer_pretty(&mut *out, &document)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+/// Write a plan as a table or JSON document. Other formats fall back to the
+/// table.
+pub fn write_plan(out: &mut dyn Write, plan: &Plan, format: Format) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, plan)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{:>30} | {:>10} | {:>12} | {:>12}",
+        "method", "actions", "capital", "expected"
+    )?;
+    for step in &plan.steps {
+        writeln!(
+            out,
+            "{:>30} | {:>10} | {:>12} | {:>12}",
+            step.name,
+            fmt_int(&step.actions),
+            fmt_int(&step.capital),
+            fmt_int(&step.expected_profit),
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:>30} | {:>10} | {:>12} | {:>12}",
+        "buy", "quantity", "price", "total"
+    )?;
+    for purchase in &plan.shopping_list {
+        writeln!(
+            out,
+            "{:>30} | {:>10} | {:>12} | {:>12}",
+            purchase.name,
+            fmt_int(&purchase.quantity),
+            fmt_int(&purchase.price),
+            fmt_int(&purchase.total),
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Using {} of {} gp, expecting {} gp profit per limit window",
+        fmt_int(&plan.capital),
+        fmt_int(&plan.bankroll),
+        fmt_int(&plan.expected_profit),
+    )?;
+    Ok(())
+}
+
+/// `5m ago` for a unix timestamp
+fn age(timestamp: usize, now: u64) -> String {
+    ago(now.saturating_sub(timestamp as u64))
+}
+
+/// `5m ago` for a number of seconds
+fn ago(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3_600 => format!("{}m ago", secs / 60),
+        3_600..86_400 => format!("{}h ago", secs / 3_600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+fn opt_int<T: num_format::ToFormattedString>(value: Option<T>) -> String {
+    value
+        .map(|v| fmt_int(&v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Write an item card as a list of fields or a JSON document. Other formats
+/// fall back to the list.
+pub fn write_item(out: &mut dyn Write, card: &Card, format: Format) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, card)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let price = |price: Option<usize>, time: Option<usize>| match time {
+        Some(time) if price.is_some() => format!("{} ({})", opt_int(price), age(time, now)),
+        _ => opt_int(price),
+    };
+    let recipes = |names: &[String]| {
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+
+    writeln!(out, "{} ({})", card.name, card.id)?;
+    writeln!(out, "{:>12}: {}", "examine", card.examine)?;
+    writeln!(
+        out,
+        "{:>12}: {}",
+        "members",
+        if card.members { "yes" } else { "no" }
+    )?;
+    writeln!(out, "{:>12}: {}", "buy limit", opt_int(card.limit))?;
+    writeln!(out, "{:>12}: {}", "low alch", opt_int(card.low_alch))?;
+    writeln!(out, "{:>12}: {}", "high alch", opt_int(card.high_alch))?;
+    writeln!(out, "{:>12}: {}", "high", price(card.high, card.high_time))?;
+    writeln!(out, "{:>12}: {}", "low", price(card.low, card.low_time))?;
+    writeln!(out, "{:>12}: {}", "spread", opt_int(card.spread))?;
+    writeln!(out, "{:>12}: {}", "tax", opt_int(card.tax))?;
+    writeln!(out, "{:>12}: {}", "margin", opt_int(card.margin))?;
+    writeln!(out, "{:>12}: {}", "volume", fmt_int(&card.volume))?;
+    writeln!(out, "{:>12}: {}", "consumed by", recipes(&card.consumed_by))?;
+    writeln!(out, "{:>12}: {}", "produced by", recipes(&card.produced_by))?;
+    Ok(())
+}
+
+/// Write the price history of `item` as a table or JSON document. Other formats
+/// fall back to the table.
+pub fn write_history(
+    out: &mut dyn Write,
+    item: &Item,
+    points: &[PricePoint],
+    format: Format,
+) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, points)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    writeln!(out, "{} ({})", item.name, item.id)?;
+    writeln!(
+        out,
+        "{:>12} | {:>12} | {:>12} | {:>12} | {:>12}",
+        "timestamp", "high", "high volume", "low", "low volume"
+    )?;
+    for point in points {
+        writeln!(
+            out,
+            "{:>12} | {:>12} | {:>12} | {:>12} | {:>12}",
+            point.timestamp,
+            opt_int(point.price.avg_high_price),
+            fmt_int(&point.price.high_price_volume),
+            opt_int(point.price.avg_low_price),
+            fmt_int(&point.price.low_price_volume),
+        )?;
+    }
+    Ok(())
+}
+
+/// Write the recipes as a table or JSON document. Other formats fall back to
+/// the table.
+pub fn write_recipes(
+    out: &mut dyn Write,
+    cache: &Cache,
+    recipes: &RecipeBook,
+    format: Format,
+) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, recipes)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    let items = |items: &mut dyn Iterator<Item = (&ItemRef, usize)>| {
+        items
+            .map(|(item, quantity)| format!("{quantity} x {}", item_name(cache, item)))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    writeln!(
+        out,
+        "{:>30} | {:>10} | {:>40} | outputs",
+        "name", "category", "inputs"
+    )?;
+    for recipe in &recipes.recipes {
+        let inputs = items(
+            &mut recipe
+                .inputs
+                .iter()
+                .map(|i| (&i.item, i.quantity))
+                .chain(recipe.runes.iter().map(|r| (&r.item, r.quantity))),
+        );
+        let outputs = items(&mut recipe.outputs.iter().map(|o| (&o.item, o.quantity)));
+        writeln!(
+            out,
+            "{:>30} | {:>10} | {:>40} | {}",
+            name(cache, recipe),
+            recipe.category,
+            inputs,
+            outputs
+        )?;
+    }
+    for potion in &recipes.potions {
+        // Any of the doses can be decanted into any other
+        let doses = potion
+            .doses
+            .iter()
+            .map(|dose| item_name(cache, &dose.item))
+            .collect::<Vec<_>>()
+            .join("; ");
+        writeln!(
+            out,
+            "{:>30} | {:>10} | {:>40} | {}",
+            potion.name, "decanting", doses, doses
+        )?;
+    }
+    Ok(())
+}
+
+/// Write every stage of each chain, the best segment up to each stage and the
+/// whole chain as a table or JSON document. Other formats fall back to the
+/// table.
+pub fn write_chains(out: &mut dyn Write, chains: &[ChainReport], format: Format) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, chains)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    for chain in chains {
+        writeln!(out, "{}", chain.items.join(" -> "))?;
+        writeln!(
+            out,
+            "{:>10} | {:>30} | {:>30} | {:>12} | {:>12} | {:>12}",
+            "", "buy", "sell", "profit", "cost", "gp/hr"
+        )?;
+        let rows = chain
+            .stages
+            .iter()
+            .map(|s| ("stage", s))
+            .chain(chain.best.iter().map(|s| ("best", s)))
+            .chain([("whole", &chain.whole)]);
+        for (kind, segment) in rows {
+            match segment {
+                Some(s) => writeln!(
+                    out,
+                    "{:>10} | {:>30} | {:>30} | {:>12} | {:>12} | {:>12}",
+                    kind,
+                    s.buy,
+                    s.sell,
+                    fmt_int(&s.entry.profit),
+                    fmt_int(&s.entry.cost),
+                    gp_hour(&s.entry),
+                )?,
+                None => writeln!(
+                    out,
+                    "{kind:>10} | {:>30} | {:>30} | {:>12} | {:>12} | {:>12}",
+                    "-", "-", "-", "-", "-"
+                )?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write the flips as a table or JSON document, quoting how long ago the older
+/// of the two prices traded. Other formats fall back to the table.
+pub fn write_flips(out: &mut dyn Write, flips: &[Flip], format: Format) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, flips)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{:>30} | {:>10} | {:>10} | {:>8} | {:>8} | {:>8} | {:>10} | {:>8} | {:>8} | {:>10} | {:>12}",
+        "name", "buy", "sell", "margin", "roi", "quoted", "volume", "buy vol", "sell vol", "fillable", "expected"
+    )?;
+    for flip in flips {
+        writeln!(
+            out,
+            "{:>30} | {:>10} | {:>10} | {:>8} | {:>7.1}% | {:>8} | {:>10} | {:>8} | {:>8} | {:>10} | {:>12}",
+            flip.name,
+            fmt_int(&flip.buy),
+            fmt_int(&flip.sell),
+            fmt_int(&flip.margin),
+            flip.roi * 100.0,
+            ago(flip.buy_age.max(flip.sell_age)),
+            fmt_int(&flip.volume),
+            opt_int(flip.buy_volume),
+            opt_int(flip.sell_volume),
+            fmt_int(&flip.fillable),
+            fmt_int(&flip.expected_profit),
+        )?;
+    }
+    Ok(())
+}
+
+/// `1.2 MB` for a number of bytes
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{bytes} B"),
+        1_000..1_000_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        _ => format!("{:.1} MB", bytes as f64 / 1e6),
+    }
+}
+
+/// Write the status of the cached datasets as a table or JSON document. Other
+/// formats fall back to the table.
+pub fn write_cache_status(out: &mut dyn Write, status: &[Status], format: Format) -> Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, status)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    writeln!(
+        out,
+        "{:>10} | {:>12} | {:>10} | {:>10} | path",
+        "dataset", "fetched", "items", "size"
+    )?;
+    for s in status {
+        writeln!(
+            out,
+            "{:>10} | {:>12} | {:>10} | {:>10} | {}",
+            s.name,
+            s.metadata
+                .as_ref()
+                .map(|meta| age(meta.fetched_at as usize, now))
+                .unwrap_or_else(|| "-".to_string()),
+            opt_int(s.items),
+            s.size.map(size).unwrap_or_else(|| "-".to_string()),
+            s.path.display(),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,266 @@
+//! Capital constrained planning across every method in a report.
+//!
+//! Methods are picked greedily by expected return on the gp they tie up, doing
+//! as many actions of each as the bankroll and the remaining buy limits allow.
+//! Buy limits are shared between methods, so two methods buying the same input
+//! can't both use its whole limit within one limit window.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::{
+    cache::Cache,
+    makers::{Line, ReportEntry, ID_COINS},
+};
+
+/// Parse an amount of gp such as `50m`, `1.5b`, `250k` or `1000000`
+pub fn parse_gp(value: &str) -> Result<usize, String> {
+    let value = value.trim().to_lowercase().replace([',', '_'], "");
+    let (number, multiplier) = match value.chars().last() {
+        Some('k') => (&value[..value.len() - 1], 1_000.0),
+        Some('m') => (&value[..value.len() - 1], 1_000_000.0),
+        Some('b') => (&value[..value.len() - 1], 1_000_000_000.0),
+        _ => (value.as_str(), 1.0),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid amount of gp {value:?}, expected e.g. 50m"))?;
+    if !number.is_finite() {
+        return Err(format!("invalid amount of gp {value:?}, expected e.g. 50m"));
+    }
+    if number.is_sign_negative() {
+        return Err(format!("invalid amount of gp {value:?}, must be positive"));
+    }
+    Ok((number * multiplier) as usize)
+}
+
+/// A method picked by the planner
+#[derive(Clone, Debug, Serialize)]
+pub struct Step {
+    pub name: String,
+    pub category: String,
+    pub actions: usize,
+    pub capital: usize,
+    pub expected_profit: isize,
+}
+
+/// An item to buy for the plan
+#[derive(Clone, Debug, Serialize)]
+pub struct Purchase {
+    pub id: usize,
+    pub name: String,
+    pub quantity: usize,
+    pub price: usize,
+    pub total: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Plan {
+    pub bankroll: usize,
+    pub capital: usize,
+    pub expected_profit: isize,
+    pub steps: Vec<Step>,
+    pub shopping_list: Vec<Purchase>,
+}
+
+/// Pick the methods among `entries` that make the most expected profit in a
+/// single limit window using at most `bankroll` gp.
+pub fn plan(cache: &Cache, entries: &[ReportEntry], bankroll: usize) -> Plan {
+    let mut candidates = entries
+        .iter()
+        .filter(|e| e.actions > 0 && e.cost > 0 && e.realisable() > 0.0)
+        .collect::<Vec<_>>();
+    // Expected profit per gp spent
+    let expected_roi = |e: &ReportEntry| e.realisable() / e.cost as f64;
+    candidates.sort_by(|a, b| expected_roi(b).total_cmp(&expected_roi(a)));
+
+    // Items without a buy limit are kept as `None` and never cap an entry
+    let mut remaining_limits: HashMap<usize, Option<usize>> = HashMap::new();
+    let mut purchases: BTreeMap<usize, Purchase> = BTreeMap::new();
+    let mut plan = Plan {
+        bankroll,
+        ..Default::default()
+    };
+
+    for entry in candidates {
+        let cost_per_action = entry.cost as f64 / entry.actions as f64;
+        let profit_per_action = entry.profit as f64 / entry.actions as f64;
+
+        // Only as many actions as are expected to fill in the window
+        let mut actions =
+            (entry.realisable() / entry.profit as f64 * entry.actions as f64) as usize;
+        actions =
+            actions.min((bankroll.saturating_sub(plan.capital) as f64 / cost_per_action) as usize);
+        // An action may use less than one of an item, e.g. decanting a 4-dose
+        // potion into 1-dose ones, so quantities are scaled rather than taken
+        // per action
+        let quantity = |line: &Line, actions: usize| line.quantity * actions / entry.actions;
+        for line in entry.inputs.iter().filter(|l| l.id != ID_COINS) {
+            if line.quantity == 0 {
+                continue;
+            }
+            let remaining = *remaining_limits
+                .entry(line.id)
+                .or_insert_with(|| cache.get(line.id).ok().and_then(|item| item.item.limit));
+            if let Some(remaining) = remaining {
+                actions = actions.min(remaining * entry.actions / line.quantity);
+            }
+        }
+        if actions == 0 {
+            continue;
+        }
+
+        let mut capital = 0;
+        for line in &entry.inputs {
+            let quantity = quantity(line, actions);
+            capital += quantity * line.price;
+            if line.id == ID_COINS {
+                continue;
+            }
+            if let Some(Some(remaining)) = remaining_limits.get_mut(&line.id) {
+                *remaining -= quantity;
+            }
+            let purchase = purchases.entry(line.id).or_insert_with(|| Purchase {
+                id: line.id,
+                name: line.name.clone(),
+                quantity: 0,
+                price: line.price,
+                total: 0,
+            });
+            purchase.quantity += quantity;
+            purchase.total += quantity * line.price;
+        }
+
+        let expected_profit = (profit_per_action * actions as f64) as isize;
+        plan.capital += capital;
+        plan.expected_profit += expected_profit;
+        plan.steps.push(Step {
+            name: entry.name.clone(),
+            category: entry.category.clone(),
+            actions,
+            capital,
+            expected_profit,
+        });
+    }
+
+    plan.shopping_list = purchases.into_values().collect();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::PriceSide;
+
+    const HERB: usize = 1;
+    const SEED: usize = 2;
+
+    /// Herbs and seeds with a limit of 1,000, both costing 100 gp
+    fn cache(volume: usize) -> Cache {
+        Cache::default()
+            .with_item(HERB, "Herb", 1_000, (100, 110), volume)
+            .with_item(SEED, "Seed", 1_000, (100, 110), volume)
+    }
+
+    /// Buying `actions` of `input` to make 50 gp on each
+    fn entry(cache: &Cache, name: &str, input: usize, actions: usize) -> ReportEntry {
+        let item = cache.get(input).unwrap();
+        ReportEntry::new(
+            name,
+            "test",
+            vec![Line::new(&item, PriceSide::Low, actions).unwrap()],
+            vec![Line::coins(150 * actions)],
+            actions,
+            item.volume,
+            actions,
+        )
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_gp("50m"), Ok(50_000_000));
+        assert_eq!(parse_gp("1.5B"), Ok(1_500_000_000));
+        assert_eq!(parse_gp("1,000"), Ok(1_000));
+        for invalid in ["-5m", "nan", "inf", "infk", "5x", ""] {
+            assert!(parse_gp(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn expected_profit_of_actions_that_fill() {
+        // A sixth of the daily volume fills in a window, so half the limit
+        let cache = cache(3_000);
+        let plan = plan(&cache, &[entry(&cache, "Half", HERB, 1_000)], usize::MAX);
+
+        assert_eq!(plan.steps[0].actions, 500);
+        assert_eq!(plan.steps[0].capital, 50_000);
+        assert_eq!(plan.expected_profit, 500 * 50);
+    }
+
+    #[test]
+    fn bankroll_caps_capital() {
+        let cache = cache(1_000_000);
+        let entries = [
+            entry(&cache, "Herb", HERB, 1_000),
+            entry(&cache, "Seed", SEED, 1_000),
+        ];
+        let plan = plan(&cache, &entries, 150_000);
+
+        assert_eq!(plan.capital, 150_000);
+        let actions: Vec<_> = plan.steps.iter().map(|s| s.actions).collect();
+        assert_eq!(actions, [1_000, 500]);
+        assert_eq!(plan.expected_profit, 1_500 * 50);
+    }
+
+    #[test]
+    fn methods_share_buy_limits() {
+        let cache = cache(1_000_000);
+        let entries = [
+            entry(&cache, "First", HERB, 600),
+            entry(&cache, "Second", HERB, 600),
+        ];
+        let plan = plan(&cache, &entries, usize::MAX);
+
+        let actions: Vec<_> = plan.steps.iter().map(|s| s.actions).collect();
+        assert_eq!(actions, [600, 400]);
+        assert_eq!(plan.shopping_list.len(), 1);
+        assert_eq!(plan.shopping_list[0].quantity, 1_000);
+    }
+
+    #[test]
+    fn actions_using_part_of_an_item() {
+        let cache = cache(1_000_000);
+        // 4,000 actions out of 1,000 herbs, like decanting 4-dose potions
+        let entry = ReportEntry {
+            actions: 4_000,
+            ..entry(&cache, "Quarter", HERB, 1_000)
+        };
+        let plan = plan(&cache, &[entry], 50_000);
+
+        assert_eq!(plan.steps[0].actions, 2_000);
+        assert_eq!(plan.shopping_list[0].quantity, 500);
+        assert_eq!(plan.capital, 50_000);
+    }
+
+    #[test]
+    fn inputs_without_a_limit() {
+        let mut cache = cache(1_000_000);
+        cache.items.get_mut(&SEED).unwrap().limit = None;
+        // Each action needs a herb and a seed
+        let line = |id| Line::new(&cache.get(id).unwrap(), PriceSide::Low, 1_000).unwrap();
+        let entry = ReportEntry::new(
+            "Both",
+            "test",
+            vec![line(HERB), line(SEED)],
+            vec![Line::coins(250 * 1_000)],
+            1_000,
+            1_000_000,
+            1_000,
+        );
+        let plan = plan(&cache, &[entry], usize::MAX);
+
+        assert_eq!(plan.steps[0].actions, 1_000);
+        assert_eq!(plan.shopping_list.len(), 2);
+    }
+}
@@ -0,0 +1,193 @@
+//! Ranking of report entries beyond raw profit.
+//!
+//! Buy limits reset every [`LIMIT_WINDOW_HOURS`] hours, so a method can be done
+//! at most once per window. How much of that can actually be realised depends
+//! on how much of the item trades in a window and on how long the actions take
+//! to do by hand.
+
+use crate::makers::ReportEntry;
+
+/// How often GE buy limits reset
+pub const LIMIT_WINDOW_HOURS: f64 = 4.0;
+
+/// How much of an item with `daily_volume` can be expected to trade within a
+/// limit window
+pub fn window_volume(daily_volume: usize) -> usize {
+    (daily_volume as f64 * LIMIT_WINDOW_HOURS / 24.0) as usize
+}
+
+/// How to order the entries of a report, best first
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortBy {
+    /// Profit of using the whole buy limit
+    #[default]
+    Profit,
+    /// Profit of a single action, e.g. a single cast
+    PerAction,
+    /// Profit per hour spent doing the method
+    GpHour,
+    /// Profit relative to the capital needed
+    Roi,
+    /// Profit that can be realised in a single buy limit window
+    Realisable,
+}
+
+impl ReportEntry {
+    /// Profit as a fraction of the cost
+    pub fn roi(&self) -> f64 {
+        if self.cost == 0 {
+            return 0.0;
+        }
+        self.profit as f64 / self.cost as f64
+    }
+
+    /// Profit of doing the method once
+    pub fn profit_per_action(&self) -> f64 {
+        if self.actions == 0 {
+            return 0.0;
+        }
+        self.profit as f64 / self.actions as f64
+    }
+
+    /// Fraction of the limit that can be expected to fill within a limit window,
+    /// estimated from the daily volume traded.
+    pub fn fill(&self) -> f64 {
+        if self.limit == 0 {
+            return 0.0;
+        }
+        let window_volume = self.volume as f64 * LIMIT_WINDOW_HOURS / 24.0;
+        (window_volume / self.limit as f64).min(1.0)
+    }
+
+    /// Hours needed to do every action of the entry, if known
+    pub fn hours(&self) -> Option<f64> {
+        self.actions_per_hour
+            .filter(|aph| *aph > 0)
+            .map(|aph| self.actions as f64 / aph as f64)
+    }
+
+    /// Profit per hour spent doing the method, if its pace is known
+    pub fn gp_hour(&self) -> Option<f64> {
+        self.hours()
+            .filter(|hours| *hours > 0.0)
+            .map(|hours| self.profit as f64 / hours)
+    }
+
+    /// Expected profit in a single limit window, limited by how much of the item
+    /// can be traded and by how many actions can be done in the window.
+    pub fn realisable(&self) -> f64 {
+        let doable = match self.hours() {
+            Some(hours) if hours > LIMIT_WINDOW_HOURS => LIMIT_WINDOW_HOURS / hours,
+            _ => 1.0,
+        };
+        self.profit as f64 * self.fill().min(doable)
+    }
+}
+
+/// Sort `entries` best first
+pub fn sort(entries: &mut [ReportEntry], by: SortBy) {
+    match by {
+        SortBy::Profit => {
+            entries.sort();
+            entries.reverse();
+        }
+        SortBy::PerAction => {
+            entries.sort_by(|a, b| b.profit_per_action().total_cmp(&a.profit_per_action()))
+        }
+        SortBy::GpHour => entries.sort_by(|a, b| {
+            let gp_hour = |e: &ReportEntry| e.gp_hour().unwrap_or(f64::NEG_INFINITY);
+            gp_hour(b).total_cmp(&gp_hour(a))
+        }),
+        SortBy::Roi => entries.sort_by(|a, b| b.roi().total_cmp(&a.roi())),
+        SortBy::Realisable => entries.sort_by(|a, b| b.realisable().total_cmp(&a.realisable())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::makers::Line;
+
+    /// An entry costing `cost` and selling for `gross`, both in untaxed coins
+    fn entry(name: &str, cost: usize, gross: usize, volume: usize) -> ReportEntry {
+        ReportEntry::new(
+            name,
+            "test",
+            vec![Line::coins(cost)],
+            vec![Line::coins(gross)],
+            1_000,
+            volume,
+            1_000,
+        )
+    }
+
+    fn names(entries: &[ReportEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn fill() {
+        // A limit window is a sixth of a day
+        assert_eq!(entry("", 0, 0, 3_000).fill(), 0.5);
+        assert_eq!(entry("", 0, 0, 60_000).fill(), 1.0);
+        assert_eq!(
+            ReportEntry {
+                limit: 0,
+                ..entry("", 0, 0, 3_000)
+            }
+            .fill(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn gp_hour() {
+        let entry = |aph| ReportEntry {
+            actions_per_hour: aph,
+            ..entry("", 1_000, 11_000, 0)
+        };
+        // 1,000 actions at 500 an hour take 2 hours
+        assert_eq!(entry(Some(500)).gp_hour(), Some(5_000.0));
+        assert_eq!(entry(Some(0)).gp_hour(), None);
+        assert_eq!(entry(None).gp_hour(), None);
+    }
+
+    #[test]
+    fn realisable() {
+        // Half the limit fills in a window
+        let half = entry("", 0, 10_000, 3_000);
+        assert_eq!(half.realisable(), 5_000.0);
+        // 8 hours of actions, so only half of them fit in a window
+        let slow = ReportEntry {
+            actions_per_hour: Some(125),
+            ..entry("", 0, 10_000, 60_000)
+        };
+        assert_eq!(slow.realisable(), 5_000.0);
+    }
+
+    #[test]
+    fn sort() {
+        let mut entries = vec![
+            ReportEntry {
+                actions_per_hour: Some(100),
+                ..entry("slow", 1_000, 31_000, 60_000)
+            },
+            ReportEntry {
+                actions_per_hour: Some(10_000),
+                ..entry("fast", 10_000, 30_000, 60_000)
+            },
+            entry("cheap", 100, 10_100, 60_000),
+            entry("illiquid", 1_000, 51_000, 600),
+        ];
+
+        super::sort(&mut entries, SortBy::Profit);
+        assert_eq!(names(&entries), ["illiquid", "slow", "fast", "cheap"]);
+        super::sort(&mut entries, SortBy::GpHour);
+        // Entries without a pace come last
+        assert_eq!(&names(&entries)[..2], ["fast", "slow"]);
+        super::sort(&mut entries, SortBy::Roi);
+        assert_eq!(names(&entries), ["cheap", "illiquid", "slow", "fast"]);
+        super::sort(&mut entries, SortBy::Realisable);
+        assert_eq!(names(&entries), ["fast", "slow", "cheap", "illiquid"]);
+    }
+}
@@ -0,0 +1,341 @@
+//! Data driven definitions of money making methods.
+//!
+//! A recipe describes a single action: the items it consumes, the items it
+//! produces, the runes of any spell that is cast and any coins paid per action.
+//! How many actions can be done is capped by the buy limit of the limiting
+//! item. The built-in recipes live in `recipes/default.toml` and more can be
+//! loaded from TOML or JSON files.
+//!
+//! Potions are listed separately by their dose variants, as any dose can be
+//! decanted into any other.
+
+use std::path::Path;
+
+use eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    item::{ItemError, PriceSide},
+    resolve::{ResolveError, Resolver},
+};
+
+const DEFAULT_RECIPES: &str = include_str!("../recipes/default.toml");
+
+fn default_quantity() -> usize {
+    1
+}
+
+fn default_output_side() -> PriceSide {
+    PriceSide::High
+}
+
+/// An item referred to by its id or by its name, e.g. `1761` or `"Soft clay"`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ItemRef {
+    Id(usize),
+    Name(String),
+}
+
+impl ItemRef {
+    /// Id of the item. Names are replaced with ids by [`RecipeBook::resolve`]
+    /// as soon as the item mapping is loaded, so only a recipe used before then
+    /// has no id.
+    pub fn id(&self) -> Result<usize, ItemError> {
+        match self {
+            ItemRef::Id(id) => Ok(*id),
+            ItemRef::Name(name) => Err(ItemError::Unresolved { name: name.clone() }),
+        }
+    }
+
+    fn resolve(&mut self, resolver: &Resolver) -> Result<(), ResolveError> {
+        if let ItemRef::Name(name) = self {
+            *self = ItemRef::Id(resolver.resolve(name)?);
+        }
+        Ok(())
+    }
+}
+
+/// An item consumed by a recipe, bought at `price`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Input {
+    pub item: ItemRef,
+    /// Name the item is expected to have, checked by `validate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "default_quantity")]
+    pub quantity: usize,
+    #[serde(default)]
+    pub price: PriceSide,
+}
+
+/// An item produced by a recipe, sold at `price`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Output {
+    pub item: ItemRef,
+    /// Name the item is expected to have, checked by `validate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "default_quantity")]
+    pub quantity: usize,
+    #[serde(default = "default_output_side")]
+    pub price: PriceSide,
+}
+
+/// A rune used by the spell cast for each action. Runes are priced at their
+/// average price.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rune {
+    pub item: ItemRef,
+    /// Name the item is expected to have, checked by `validate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "default_quantity")]
+    pub quantity: usize,
+}
+
+/// A dose variant of a potion
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dose {
+    pub item: ItemRef,
+    /// Name the item is expected to have, checked by `validate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    /// Name shown in the report. `{input}` and `{output}` are replaced with the
+    /// names of the first input and output. Defaults to `{output}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub category: String,
+    pub inputs: Vec<Input>,
+    pub outputs: Vec<Output>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runes: Vec<Rune>,
+    /// 4-dose form of the first output, which must be a dose of the same
+    /// `[[potion]]`. The output is sold in whichever form makes more, decanting
+    /// every four 3-dose potions into three 4-dose ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decant: Option<Dose>,
+    /// Coins paid for each action, e.g. the sawmill fee of plank make
+    #[serde(default)]
+    pub fee: usize,
+    /// Item whose buy limit caps the number of actions. Defaults to the first
+    /// input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limiting: Option<ItemRef>,
+    /// Fixed number of the limiting item that can be used, instead of its buy
+    /// limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// How many actions can be done in an hour, including banking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actions_per_hour: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl Recipe {
+    /// Id of the item whose buy limit caps the number of actions
+    pub fn limiting(&self) -> &ItemRef {
+        self.limiting.as_ref().unwrap_or(&self.inputs[0].item)
+    }
+
+    /// How many of the limiting item are used or made in a single action
+    pub fn limiting_quantity(&self) -> usize {
+        let limiting = self.limiting();
+        self.inputs
+            .iter()
+            .map(|i| (&i.item, i.quantity))
+            .chain(self.outputs.iter().map(|o| (&o.item, o.quantity)))
+            .find(|(item, _)| *item == limiting)
+            .map(|(_, quantity)| quantity)
+            .unwrap_or(1)
+    }
+
+    /// Ids of every item used by the recipe, leaving out unresolved names
+    pub fn ids(&self) -> Vec<usize> {
+        self.inputs
+            .iter()
+            .map(|i| &i.item)
+            .chain(self.outputs.iter().map(|o| &o.item))
+            .chain(self.runes.iter().map(|r| &r.item))
+            .chain(self.decant.iter().map(|d| &d.item))
+            .filter_map(|item| item.id().ok())
+            .collect()
+    }
+
+    /// Every item reference of the recipe
+    fn refs_mut(&mut self) -> impl Iterator<Item = &mut ItemRef> {
+        self.inputs
+            .iter_mut()
+            .map(|i| &mut i.item)
+            .chain(self.outputs.iter_mut().map(|o| &mut o.item))
+            .chain(self.runes.iter_mut().map(|r| &mut r.item))
+            .chain(self.decant.iter_mut().map(|d| &mut d.item))
+            .chain(self.limiting.as_mut())
+    }
+}
+
+/// A potion that can be decanted between its doses
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Potion {
+    pub name: String,
+    /// Every dose variant from a single dose up, e.g. `Prayer potion(1)` to
+    /// `Prayer potion(4)`
+    pub doses: Vec<Dose>,
+}
+
+impl Potion {
+    /// Number of doses of `item`, if it is one of the potion's doses
+    pub fn dose(&self, item: &ItemRef) -> Option<usize> {
+        self.doses
+            .iter()
+            .position(|d| d.item == *item)
+            .map(|i| i + 1)
+    }
+
+    /// Ids of every dose variant, leaving out unresolved names
+    pub fn ids(&self) -> Vec<usize> {
+        self.doses.iter().filter_map(|d| d.item.id().ok()).collect()
+    }
+
+    /// Every item reference of the potion
+    fn refs_mut(&mut self) -> impl Iterator<Item = &mut ItemRef> {
+        self.doses.iter_mut().map(|d| &mut d.item)
+    }
+}
+
+/// A collection of recipes and decantable potions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecipeBook {
+    #[serde(rename = "recipe", default)]
+    pub recipes: Vec<Recipe>,
+    #[serde(rename = "potion", default, skip_serializing_if = "Vec::is_empty")]
+    pub potions: Vec<Potion>,
+}
+
+impl RecipeBook {
+    /// The recipes that ship with osrs-mm
+    pub fn builtin() -> Result<Self> {
+        Self::from_toml(DEFAULT_RECIPES)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Self::checked(toml::from_str(contents)?)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self> {
+        Self::checked(serde_json::from_str(contents)?)
+    }
+
+    /// Load recipes from a `.toml` or `.json` file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => bail!("{}: expected a .toml or .json recipe file", path.display()),
+        }
+    }
+
+    fn checked(book: Self) -> Result<Self> {
+        for recipe in &book.recipes {
+            let name = recipe.name.as_deref().unwrap_or_default();
+            if recipe.inputs.is_empty() || recipe.outputs.is_empty() {
+                bail!(
+                    "{} recipe {name:?} needs at least one input and one output",
+                    recipe.category
+                );
+            }
+            let mut quantities = recipe
+                .inputs
+                .iter()
+                .map(|i| i.quantity)
+                .chain(recipe.outputs.iter().map(|o| o.quantity))
+                .chain(recipe.runes.iter().map(|r| r.quantity));
+            if quantities.any(|q| q == 0) {
+                bail!(
+                    "{} recipe {name:?} has an input, output or rune with a quantity of 0",
+                    recipe.category
+                );
+            }
+            if recipe.limit == Some(0) {
+                bail!("{} recipe {name:?} has a limit of 0", recipe.category);
+            }
+        }
+        for potion in &book.potions {
+            if potion.doses.len() < 2 {
+                bail!("potion {:?} needs at least two doses", potion.name);
+            }
+        }
+        Ok(book)
+    }
+
+    pub fn extend(&mut self, other: RecipeBook) {
+        self.recipes.extend(other.recipes);
+        self.potions.extend(other.potions);
+    }
+
+    /// Replace every item name with its id. Fails listing every name that could
+    /// not be resolved.
+    pub fn resolve(&mut self, resolver: &Resolver) -> Result<()> {
+        let mut errors = Vec::new();
+        for recipe in &mut self.recipes {
+            let category = recipe.category.clone();
+            for item in recipe.refs_mut() {
+                if let Err(err) = item.resolve(resolver) {
+                    errors.push(format!("{category} recipe: {err}"));
+                }
+            }
+        }
+        for potion in &mut self.potions {
+            let name = potion.name.clone();
+            for item in potion.refs_mut() {
+                if let Err(err) = item.resolve(resolver) {
+                    errors.push(format!("potion {name:?}: {err}"));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("failed to resolve recipe items:\n  {}", errors.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lobster(fields: &str) -> Result<RecipeBook> {
+        RecipeBook::from_toml(&format!(
+            r#"
+            [[recipe]]
+            category = "cooking"
+            {fields}
+            "#
+        ))
+    }
+
+    #[test]
+    fn zero_quantities_are_rejected() {
+        assert!(lobster("inputs = [{ item = 377 }]\noutputs = [{ item = 379 }]").is_ok());
+
+        for fields in [
+            "inputs = [{ item = 377, quantity = 0 }]\noutputs = [{ item = 379 }]",
+            "inputs = [{ item = 377 }]\noutputs = [{ item = 379, quantity = 0 }]",
+            "inputs = [{ item = 377 }]\noutputs = [{ item = 379 }]\nrunes = [{ item = 561, quantity = 0 }]",
+        ] {
+            let err = lobster(fields).unwrap_err();
+            assert!(err.to_string().contains("quantity of 0"), "{err}");
+        }
+
+        let err = lobster("inputs = [{ item = 377 }]\noutputs = [{ item = 379 }]\nlimit = 0")
+            .unwrap_err();
+        assert!(err.to_string().contains("limit of 0"), "{err}");
+    }
+}
@@ -0,0 +1,288 @@
+//! Lookup of items by name in the item mapping.
+//!
+//! Names are matched exactly first, then ignoring case and finally fuzzily. A
+//! query that matches several items equally well is reported as ambiguous
+//! rather than silently picking one of them.
+
+use std::collections::HashMap;
+
+use crate::item::Item;
+
+/// Smallest similarity for a fuzzy match to be accepted
+const FUZZY_THRESHOLD: f64 = 0.8;
+/// Fuzzy matches closer than this to the best match make the query ambiguous
+const AMBIGUITY_MARGIN: f64 = 0.05;
+/// Smallest similarity for an item to be suggested
+const SUGGESTION_THRESHOLD: f64 = 0.5;
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResolveError {
+    /// Nothing matched, along with the closest names
+    NotFound {
+        query: String,
+        suggestions: Vec<String>,
+    },
+    /// Several items matched equally well
+    Ambiguous {
+        query: String,
+        candidates: Vec<(usize, String)>,
+    },
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::NotFound { query, suggestions } if suggestions.is_empty() => {
+                write!(f, "no item named {query:?}")
+            }
+            ResolveError::NotFound { query, suggestions } => {
+                write!(
+                    f,
+                    "no item named {query:?}, did you mean {}?",
+                    suggestions.join(", ")
+                )
+            }
+            ResolveError::Ambiguous { query, candidates } => {
+                let candidates = candidates
+                    .iter()
+                    .map(|(id, name)| format!("{name} ({id})"))
+                    .collect::<Vec<_>>();
+                write!(f, "{query:?} is ambiguous: {}", candidates.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// Similarity of two strings between 0 and 1 based on their edit distance
+fn similarity(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// How well `query` matches `name`, both lowercase. A query contained in the
+/// name scores higher the more of the name it covers.
+fn score(query: &str, name: &str) -> f64 {
+    let edit = similarity(query, name);
+    if name.contains(query) {
+        let coverage = query.len() as f64 / name.len() as f64;
+        edit.max(0.7 + 0.3 * coverage)
+    } else {
+        edit
+    }
+}
+
+pub struct Resolver<'a> {
+    items: &'a HashMap<usize, Item>,
+    by_name: HashMap<&'a str, Vec<usize>>,
+    by_lowercase: HashMap<String, Vec<usize>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(items: &'a HashMap<usize, Item>) -> Self {
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut by_lowercase: HashMap<String, Vec<usize>> = HashMap::new();
+        for item in items.values() {
+            by_name.entry(&item.name).or_default().push(item.id);
+            by_lowercase
+                .entry(item.name.to_lowercase())
+                .or_default()
+                .push(item.id);
+        }
+
+        Self {
+            items,
+            by_name,
+            by_lowercase,
+        }
+    }
+
+    fn unique(&self, query: &str, ids: &[usize]) -> Result<usize, ResolveError> {
+        match ids {
+            [id] => Ok(*id),
+            _ => {
+                let mut candidates = ids
+                    .iter()
+                    .map(|id| (*id, self.items[id].name.clone()))
+                    .collect::<Vec<_>>();
+                candidates.sort();
+                Err(ResolveError::Ambiguous {
+                    query: query.to_string(),
+                    candidates,
+                })
+            }
+        }
+    }
+
+    /// Items ranked by how well their name matches `query`, best first
+    pub fn search(&self, query: &str) -> Vec<(usize, f64)> {
+        let query = query.trim().to_lowercase();
+        let mut matches = self
+            .by_lowercase
+            .iter()
+            .map(|(name, ids)| (ids, score(&query, name)))
+            .flat_map(|(ids, score)| ids.iter().map(move |id| (*id, score)))
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        matches
+    }
+
+    /// Id of the item called `query`. A query that is a known id resolves to
+    /// that id.
+    pub fn resolve(&self, query: &str) -> Result<usize, ResolveError> {
+        if let Ok(id) = query.trim().parse::<usize>() {
+            if self.items.contains_key(&id) {
+                return Ok(id);
+            }
+        }
+        if let Some(ids) = self.by_name.get(query) {
+            return self.unique(query, ids);
+        }
+        if let Some(ids) = self.by_lowercase.get(&query.trim().to_lowercase()) {
+            return self.unique(query, ids);
+        }
+
+        let matches = self.search(query);
+        match matches.first() {
+            Some((id, best)) if *best >= FUZZY_THRESHOLD => {
+                let close = matches
+                    .iter()
+                    .take_while(|(_, score)| best - score < AMBIGUITY_MARGIN)
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>();
+                if close.len() == 1 {
+                    Ok(*id)
+                } else {
+                    self.unique(query, &close)
+                }
+            }
+            _ => Err(ResolveError::NotFound {
+                query: query.to_string(),
+                suggestions: matches
+                    .iter()
+                    .take_while(|(_, score)| *score >= SUGGESTION_THRESHOLD)
+                    .take(MAX_SUGGESTIONS)
+                    .map(|(id, _)| self.items[id].name.clone())
+                    .collect(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(names: &[(usize, &str)]) -> HashMap<usize, Item> {
+        names
+            .iter()
+            .map(|&(id, name)| {
+                let item = Item {
+                    id,
+                    name: name.to_string(),
+                    examine: String::new(),
+                    members: false,
+                    low_alch: None,
+                    high_alch: None,
+                    limit: None,
+                    value: 0,
+                };
+                (id, item)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exact_and_case_insensitive() {
+        let items = items(&[(1761, "Soft clay"), (434, "Clay")]);
+        let resolver = Resolver::new(&items);
+
+        assert_eq!(resolver.resolve("Soft clay"), Ok(1761));
+        assert_eq!(resolver.resolve("  sOFT CLAY "), Ok(1761));
+        assert_eq!(resolver.resolve("434"), Ok(434));
+    }
+
+    #[test]
+    fn typos() {
+        let items = items(&[(1761, "Soft clay"), (434, "Clay")]);
+        let resolver = Resolver::new(&items);
+
+        assert_eq!(resolver.resolve("Soft cly"), Ok(1761));
+        let Err(ResolveError::NotFound { suggestions, .. }) = resolver.resolve("Sift claw") else {
+            panic!("expected no match");
+        };
+        assert_eq!(suggestions, ["Soft clay"]);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let items = items(&[(1, "Rune bar"), (2, "Rune bat"), (3, "Same"), (4, "same")]);
+        let resolver = Resolver::new(&items);
+
+        // Equally close to both
+        assert_eq!(
+            resolver.resolve("Rune baz"),
+            Err(ResolveError::Ambiguous {
+                query: "Rune baz".to_string(),
+                candidates: vec![(1, "Rune bar".to_string()), (2, "Rune bat".to_string())],
+            })
+        );
+        // Only the exact case is unique
+        assert_eq!(resolver.resolve("Same"), Ok(3));
+        assert!(matches!(
+            resolver.resolve("SAME"),
+            Err(ResolveError::Ambiguous { .. })
+        ));
+    }
+
+    #[test]
+    fn fuzzy_threshold() {
+        let items = items(&[(1, "abcdefghij")]);
+        let resolver = Resolver::new(&items);
+
+        // 2 of 10 characters wrong is just similar enough, 3 isn't
+        assert_eq!(similarity("abcdefghxx", "abcdefghij"), FUZZY_THRESHOLD);
+        assert_eq!(resolver.resolve("abcdefghxx"), Ok(1));
+        assert!(matches!(
+            resolver.resolve("abcdefgxxx"),
+            Err(ResolveError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn ambiguity_margin() {
+        // One and two characters off out of 10 are 0.1 apart, so the closest
+        // wins. Out of 25 they are only 0.04 apart, which is too close to call.
+        let items = items(&[
+            (1, "abcdefghij"),
+            (2, "abcdefgzzz"),
+            (3, "abcdefghijklmnopqrstuvwxy"),
+            (4, "abcdefghijklmnopqrstuvzzz"),
+        ]);
+        let resolver = Resolver::new(&items);
+
+        assert_eq!(resolver.resolve("abcdefghiz"), Ok(1));
+        assert!(matches!(
+            resolver.resolve("abcdefghijklmnopqrstuvwxz"),
+            Err(ResolveError::Ambiguous { candidates, .. }) if candidates.len() == 2
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+//! Grand Exchange sell tax.
+//!
+//! Every item sold on the GE pays a tax of [`RATE_PERCENT`] of its sell price,
+//! rounded down and capped at [`CAP`] per item. The rounding means items that
+//! sell for under 50gp are effectively untaxed. A small set of items is exempt
+//! from the tax entirely (see [`EXEMPT`]).
+
+/// Tax rate as a percentage of the sell price of a single item
+pub const RATE_PERCENT: usize = 2;
+
+/// Maximum tax paid on a single item
+pub const CAP: usize = 5_000_000;
+
+/// Items that are exempt from the GE tax
+#[rustfmt::skip]
+const EXEMPT: &[usize] = &[
+    // Old school bond
+    13190,
+    // Chisel, Gardening trowel, Glassblowing pipe, Hammer
+    1755, 5325, 1785, 2347,
+    // Needle, Pestle and mortar, Rake, Saw
+    1733, 233, 5341, 8794,
+    // Secateurs, Seed dibber, Shears, Spade, Watering can(0)
+    5329, 5343, 1735, 952, 5331,
+    // Varrock, Lumbridge, Falador, Camelot, Ardougne teleport, Teleport to house
+    8007, 8008, 8009, 8010, 8011, 8013,
+    // Kourend castle teleport
+    19651,
+];
+
+/// Tax paid when selling a single item with `id` for `price`.
+pub fn per_item(id: usize, price: usize) -> usize {
+    if EXEMPT.contains(&id) {
+        return 0;
+    }
+
+    (price * RATE_PERCENT / 100).min(CAP)
+}
+
+/// Tax paid when selling `quantity` items with `id` for `price` each.
+pub fn total(id: usize, price: usize, quantity: usize) -> usize {
+    per_item(id, price) * quantity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_down() {
+        assert_eq!(per_item(1, 49), 0);
+        assert_eq!(per_item(1, 50), 1);
+        assert_eq!(per_item(1, 99), 1);
+        assert_eq!(per_item(1, 100), 2);
+        assert_eq!(per_item(1, 1_234_567), 24_691);
+    }
+
+    #[test]
+    fn capped() {
+        assert_eq!(per_item(1, 250_000_000), CAP);
+        assert_eq!(per_item(1, 2_000_000_000), CAP);
+        assert_eq!(total(1, 2_000_000_000, 3), 3 * CAP);
+    }
+
+    #[test]
+    fn exempt() {
+        for id in EXEMPT {
+            assert_eq!(per_item(*id, 10_000_000), 0, "{id}");
+        }
+        assert_eq!(total(13190, 10_000_000, 5), 0);
+    }
+
+    #[test]
+    fn total_is_per_item() {
+        assert_eq!(total(1, 992, 10_000), 190_000);
+    }
+}
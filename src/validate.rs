@@ -0,0 +1,270 @@
+//! Checks of the recipes and potions against the item mapping.
+//!
+//! Catches ids that don't exist, items used in several roles of one recipe,
+//! such as clay being both the input and the output of humidify, and items
+//! whose name isn't the `name` the recipe expects.
+
+use std::{collections::HashMap, io::Write, path::Path};
+
+use eyre::{bail, Result};
+
+use crate::{
+    cache,
+    item::Item,
+    recipe::{ItemRef, Recipe, RecipeBook},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The id isn't in the item mapping
+    UnknownItem { id: usize },
+    /// The same item is used in several roles
+    DuplicateItem { id: usize, roles: Vec<&'static str> },
+    /// The item isn't called what the recipe expects
+    NameMismatch {
+        id: usize,
+        expected: String,
+        actual: String,
+    },
+    /// The item is only known by a name that hasn't been resolved
+    Unresolved { name: String },
+    /// The output and its decanted form aren't doses of the same potion
+    NotDecantable,
+    /// The dose of a potion isn't listed in its place, counting from 1
+    WrongDose { id: usize, doses: usize },
+}
+
+impl Problem {
+    /// Whether the recipe can't be evaluated correctly. A name mismatch may
+    /// just be an item being renamed.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Problem::NameMismatch { .. })
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::UnknownItem { id } => write!(f, "unknown item {id}"),
+            Problem::DuplicateItem { id, roles } => {
+                write!(f, "item {id} is used as {}", roles.join(" and "))
+            }
+            Problem::NameMismatch {
+                id,
+                expected,
+                actual,
+            } => write!(f, "item {id} is {actual:?}, expected {expected:?}"),
+            Problem::Unresolved { name } => write!(f, "item {name:?} hasn't been resolved"),
+            Problem::WrongDose { id, doses } => {
+                write!(
+                    f,
+                    "item {id} isn't the {doses}-dose form, list doses from 1 up"
+                )
+            }
+            Problem::NotDecantable => write!(
+                f,
+                "the output and its decanted form aren't doses of the same potion"
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub recipe: String,
+    pub problem: Problem,
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = if self.problem.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        write!(f, "{level}: {}: {}", self.recipe, self.problem)
+    }
+}
+
+/// `Soft clay (clay)`, naming the recipe by its first output
+fn label(items: &HashMap<usize, Item>, recipe: &Recipe) -> String {
+    let output = &recipe.outputs[0];
+    let name = match (&output.item, &output.name) {
+        (ItemRef::Id(id), _) if items.contains_key(id) => items[id].name.clone(),
+        (_, Some(name)) | (ItemRef::Name(name), None) => name.clone(),
+        (ItemRef::Id(id), None) => format!("#{id}"),
+    };
+    format!("{name} ({})", recipe.category)
+}
+
+/// Problems with the items of a recipe or potion, given as their role, item and
+/// expected name
+fn problems(
+    refs: &[(&'static str, &ItemRef, &Option<String>)],
+    items: &HashMap<usize, Item>,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut roles: Vec<(usize, Vec<&'static str>)> = Vec::new();
+    for (role, item, expected) in refs {
+        let id = match item {
+            ItemRef::Id(id) => id,
+            ItemRef::Name(name) => {
+                problems.push(Problem::Unresolved { name: name.clone() });
+                continue;
+            }
+        };
+        match roles.iter_mut().find(|(seen, _)| seen == id) {
+            Some((_, seen_roles)) if !seen_roles.contains(role) => seen_roles.push(role),
+            Some(_) => {}
+            None => roles.push((*id, vec![role])),
+        }
+
+        match (items.get(id), expected) {
+            (None, _) => problems.push(Problem::UnknownItem { id: *id }),
+            (Some(item), Some(expected)) if !item.name.eq_ignore_ascii_case(expected) => problems
+                .push(Problem::NameMismatch {
+                    id: *id,
+                    expected: expected.clone(),
+                    actual: item.name.clone(),
+                }),
+            _ => {}
+        }
+    }
+    for (id, roles) in roles {
+        if roles.len() > 1 {
+            problems.push(Problem::DuplicateItem { id, roles });
+        }
+    }
+    problems
+}
+
+/// Every problem with the recipes and potions of `book`, which must have been
+/// resolved
+pub fn check(book: &RecipeBook, items: &HashMap<usize, Item>) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut push = |label: String, mut problems: Vec<Problem>| {
+        problems.dedup();
+        issues.extend(problems.into_iter().map(|problem| Issue {
+            recipe: label.clone(),
+            problem,
+        }));
+    };
+
+    for recipe in &book.recipes {
+        let refs = recipe
+            .inputs
+            .iter()
+            .map(|i| ("input", &i.item, &i.name))
+            .chain(recipe.outputs.iter().map(|o| ("output", &o.item, &o.name)))
+            .chain(recipe.runes.iter().map(|r| ("rune", &r.item, &r.name)))
+            .chain(
+                recipe
+                    .decant
+                    .iter()
+                    .map(|d| ("decanted output", &d.item, &d.name)),
+            )
+            .collect::<Vec<_>>();
+
+        let mut problems = problems(&refs, items);
+        match &recipe.limiting {
+            Some(ItemRef::Id(id))
+                if !items.contains_key(id)
+                    && !refs.iter().any(|(_, item, _)| *item == &ItemRef::Id(*id)) =>
+            {
+                problems.push(Problem::UnknownItem { id: *id });
+            }
+            Some(ItemRef::Name(name)) => problems.push(Problem::Unresolved { name: name.clone() }),
+            _ => {}
+        }
+        if let Some(decant) = &recipe.decant {
+            let made = &recipe.outputs[0].item;
+            if !book
+                .potions
+                .iter()
+                .any(|p| p.dose(made).is_some() && p.dose(&decant.item).is_some())
+            {
+                problems.push(Problem::NotDecantable);
+            }
+        }
+        push(label(items, recipe), problems);
+    }
+
+    for potion in &book.potions {
+        let refs = potion
+            .doses
+            .iter()
+            .map(|d| ("dose", &d.item, &d.name))
+            .collect::<Vec<_>>();
+        let mut problems = problems(&refs, items);
+        for (i, dose) in potion.doses.iter().enumerate() {
+            let (Ok(id), doses) = (dose.item.id(), i + 1) else {
+                continue;
+            };
+            let suffix = format!("({doses})");
+            if items
+                .get(&id)
+                .is_some_and(|item| !item.name.ends_with(&suffix))
+            {
+                problems.push(Problem::WrongDose { id, doses });
+            }
+        }
+        let ids = potion.ids();
+        for (i, id) in ids.iter().enumerate() {
+            if ids[..i].contains(id) {
+                problems.push(Problem::DuplicateItem {
+                    id: *id,
+                    roles: vec!["more than one dose"],
+                });
+            }
+        }
+        push(format!("{} (decanting)", potion.name), problems);
+    }
+    issues
+}
+
+/// Check the recipes before using them. Name mismatches are only warned about,
+/// any other problem is an error.
+pub fn startup(book: &RecipeBook, items: &HashMap<usize, Item>) -> Result<()> {
+    let (errors, warnings): (Vec<_>, Vec<_>) = check(book, items)
+        .into_iter()
+        .partition(|issue| issue.problem.is_error());
+    for warning in &warnings {
+        eprintln!("{warning}");
+    }
+    if !errors.is_empty() {
+        let errors = errors.iter().map(Issue::to_string).collect::<Vec<_>>();
+        bail!(
+            "invalid recipes, see `osrs-mm validate`:\n  {}",
+            errors.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Load an item mapping in the format of the `/mapping` endpoint
+pub fn load_mapping(path: &Path) -> Result<HashMap<usize, Item>> {
+    let contents = std::fs::read_to_string(path)?;
+    cache::parse_mapping(&contents)
+}
+
+/// Print every problem with the recipes of `book`, failing if there are any
+pub fn run(out: &mut dyn Write, book: &RecipeBook, items: &HashMap<usize, Item>) -> Result<()> {
+    let issues = check(book, items);
+    for issue in &issues {
+        writeln!(out, "{issue}")?;
+    }
+
+    let errors = issues.iter().filter(|i| i.problem.is_error()).count();
+    writeln!(
+        out,
+        "Checked {} recipes and {} potions: {} errors, {} warnings",
+        book.recipes.len(),
+        book.potions.len(),
+        errors,
+        issues.len() - errors
+    )?;
+    if !issues.is_empty() {
+        bail!("recipes failed validation");
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Price history synced from recorded timeseries responses, without network
+//! access.
+
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use serde_json::Value;
+
+/// An empty cache directory only used by `test`
+fn cache_dir(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Run osrs-mm with only the lobster recipe, which every timeseries fixture
+/// covers, and expect it to succeed
+fn osrs_mm(cache_dir: &PathBuf, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_osrs-mm"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("XDG_CONFIG_HOME", "tests/fixtures")
+        .arg("--cache-dir")
+        .arg(cache_dir)
+        .args([
+            "--fixtures",
+            "tests/fixtures/api",
+            "--no-default-recipes",
+            "--recipes",
+            "tests/fixtures/lobster_recipes.toml",
+        ])
+        .args(args)
+        .output()
+        .expect("failed to run osrs-mm");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn history(cache_dir: &PathBuf, query: &str) -> Value {
+    let output = osrs_mm(cache_dir, &["--format", "json", "history", "show", query]);
+    serde_json::from_slice(&output.stdout).expect("history isn't valid JSON")
+}
+
+#[test]
+fn sync_appends_without_duplicates() {
+    let dir = cache_dir("sync_appends_without_duplicates");
+
+    // Raw lobster and lobster have 3 points each, nature runes 2
+    let output = osrs_mm(&dir, &["history", "sync"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Added 8 5m price points"), "{stderr}");
+    let output = osrs_mm(&dir, &["history", "sync"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Added 0 5m price points"), "{stderr}");
+
+    let points = history(&dir, "Raw lobster");
+    let timestamps: Vec<_> = points
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| point["timestamp"].as_u64().unwrap())
+        .collect();
+    assert_eq!(timestamps, [1699999100, 1699999400, 1699999700]);
+}
+
+#[test]
+fn sync_warns_about_empty_timeseries() {
+    let dir = cache_dir("sync_warns_about_empty_timeseries");
+    let output = osrs_mm(
+        &dir,
+        &[
+            "--recipes",
+            "tests/fixtures/swordfish_recipes.toml",
+            "history",
+            "sync",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("warning: no timeseries returned for item 371, skipping it"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Added 8 5m price points"), "{stderr}");
+}
+
+#[test]
+fn report_at_uses_latest_point_before() {
+    let dir = cache_dir("report_at_uses_latest_point_before");
+    osrs_mm(&dir, &["history", "sync"]);
+
+    let output = osrs_mm(
+        &dir,
+        &[
+            "report",
+            "--format",
+            "json",
+            "--at",
+            "1699999500",
+            "--min-profit=-1000000000000",
+            "--min-volume=0",
+            "--max-capital=1000000000000",
+        ],
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("report isn't valid JSON");
+    let lobster = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["name"] == "Cooking Lobster")
+        .expect("no lobster entry");
+
+    // The point at 1699999400 has no low price for raw lobsters, so the one
+    // before it is used, while lobsters sell at 1,010 from that same point.
+    // Nothing after 1699999500 is used.
+    assert_eq!(lobster["cost"], 10_000 * 900);
+    assert_eq!(lobster["gross_before_tax"], 10_000 * 1_010);
+    // Only the items of recipes and the nature rune have a history, so nothing
+    // else is alched
+    let mut alched: Vec<_> = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["category"] == "alching")
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    alched.sort();
+    assert_eq!(
+        alched,
+        [
+            "High alch Lobster",
+            "High alch Nature rune",
+            "High alch Raw lobster"
+        ]
+    );
+}
@@ -0,0 +1,249 @@
+//! Reports evaluated against recorded API responses, without network access.
+
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use serde_json::Value;
+
+/// An empty cache directory only used by `test`
+fn cache_dir(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn osrs_mm(cache_dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_osrs-mm"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("XDG_CONFIG_HOME", "tests/fixtures")
+        .arg("--cache-dir")
+        .arg(cache_dir)
+        .args(args)
+        .output()
+        .expect("failed to run osrs-mm")
+}
+
+/// The JSON report of every recipe, unfiltered
+fn report(cache_dir: &PathBuf, client: &[&str]) -> Value {
+    let mut args = client.to_vec();
+    args.extend([
+        "report",
+        "--format",
+        "json",
+        "--min-profit=-1000000000000",
+        "--min-volume=0",
+        "--max-capital=1000000000000",
+    ]);
+    let output = osrs_mm(cache_dir, &args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("report isn't valid JSON")
+}
+
+#[test]
+fn every_recipe_is_evaluated() {
+    let dir = cache_dir("every_recipe_is_evaluated");
+    let report = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+
+    assert_eq!(report["skipped"].as_array().unwrap().len(), 0);
+    let entries = report["entries"].as_array().unwrap();
+    assert!(!entries.is_empty());
+    for entry in entries {
+        let gross = entry["gross_before_tax"].as_i64().unwrap() - entry["tax"].as_i64().unwrap();
+        assert_eq!(entry["gross"].as_i64().unwrap(), gross, "{}", entry["name"]);
+        assert_eq!(
+            entry["profit"].as_i64().unwrap(),
+            gross - entry["cost"].as_i64().unwrap(),
+            "{}",
+            entry["name"]
+        );
+    }
+}
+
+#[test]
+fn cooking_lobster() {
+    let dir = cache_dir("cooking_lobster");
+    let report = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+    let lobster = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["name"] == "Cooking Lobster")
+        .expect("no lobster entry");
+
+    // 10,000 raw lobsters bought at 939 and cooked lobsters sold at 992, paying
+    // 19 gp of tax on each
+    assert_eq!(lobster["cost"], 9_390_000);
+    assert_eq!(lobster["gross_before_tax"], 9_920_000);
+    assert_eq!(lobster["tax"], 190_000);
+    assert_eq!(lobster["profit"], 340_000);
+}
+
+#[test]
+fn offline_uses_the_cache() {
+    let dir = cache_dir("offline_uses_the_cache");
+    let fetched = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+    let offline = report(&dir, &["--offline"]);
+    assert_eq!(fetched["entries"], offline["entries"]);
+}
+
+#[test]
+fn offline_fails_without_cache() {
+    let dir = cache_dir("offline_fails_without_cache");
+    let output = osrs_mm(&dir, &["--offline", "report"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("can't be fetched offline"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn exported_cache_reproduces_the_report() {
+    let dir = cache_dir("exported_cache_reproduces_the_report");
+    let fetched = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+    let snapshot = dir.join("snapshot.json");
+    let output = osrs_mm(&dir, &["cache", "export", snapshot.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+
+    let imported = cache_dir("exported_cache_reproduces_the_report_imported");
+    let output = osrs_mm(&imported, &["cache", "import", snapshot.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let offline = report(&imported, &["--offline"]);
+    assert_eq!(fetched["entries"], offline["entries"]);
+
+    // Snapshots of another schema are rejected, leaving the cache untouched
+    let mut contents: Value =
+        serde_json::from_str(&std::fs::read_to_string(&snapshot).unwrap()).unwrap();
+    contents["schema_version"] = 999.into();
+    std::fs::write(&snapshot, contents.to_string()).unwrap();
+    let other = cache_dir("exported_cache_reproduces_the_report_other");
+    let output = osrs_mm(&other, &["cache", "import", snapshot.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("has schema version 999"), "{stderr}");
+    assert!(!osrs_mm(&other, &["--offline", "report"]).status.success());
+}
+
+#[test]
+fn report_options_before_a_subcommand_are_rejected() {
+    let dir = cache_dir("report_options_before_a_subcommand_are_rejected");
+    let output = osrs_mm(
+        &dir,
+        &[
+            "--fixtures",
+            "tests/fixtures/api",
+            "--min-profit=1",
+            "report",
+        ],
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--min-profit"), "{stderr}");
+}
+
+/// The outputs of the entry called `name`, as names and quantities
+fn outputs(report: &Value, name: &str) -> Vec<(String, u64)> {
+    let entry = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["name"] == name)
+        .unwrap_or_else(|| panic!("no entry {name}"));
+    entry["outputs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| {
+            let name = o["name"].as_str().unwrap().to_string();
+            (name, o["quantity"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn potions_are_sold_decanted_when_it_pays() {
+    let dir = cache_dir("potions_are_sold_decanted_when_it_pays");
+    let report = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+    let names: Vec<_> = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["category"] == "potions")
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+
+    // An antipoison(4) sells for 3,479 against 1,431 for a (3), so the 10,000
+    // made are decanted into 7,500
+    assert!(!names.contains(&"Antipoison(3)"));
+    assert_eq!(
+        outputs(&report, "Antipoison(4)"),
+        [("Antipoison(4)".to_string(), 7_500)]
+    );
+    // A Saradomin brew(4) sells for less than a (3)
+    assert!(!names.contains(&"Saradomin brew(4)"));
+    assert_eq!(
+        outputs(&report, "Saradomin brew(3)"),
+        [("Saradomin brew(3)".to_string(), 10_000)]
+    );
+}
+
+/// The inputs of the entry called `name`, as names and quantities
+fn inputs(report: &Value, name: &str) -> Vec<(String, u64)> {
+    let entry = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["name"] == name)
+        .unwrap_or_else(|| panic!("no entry {name}"));
+    entry["inputs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| {
+            let name = i["name"].as_str().unwrap().to_string();
+            (name, i["quantity"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn decanting_buys_doses_within_their_limit_and_volume() {
+    let dir = cache_dir("decanting_buys_doses_within_their_limit_and_volume");
+    let report = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+
+    // Hunter potion(3) sells for the most per dose, and 1,000 of them trade in
+    // a limit window, so 3,000 doses are bought. The cheapest dose per dose is
+    // (2), capped by its limit of 150, then (1), capped by the 102 trading in a
+    // window, with the rest made up of (4)
+    assert_eq!(
+        inputs(&report, "Hunter potion(1+2+4) to (3)"),
+        [
+            ("Hunter potion(2)".to_string(), 150),
+            ("Hunter potion(1)".to_string(), 102),
+            ("Hunter potion(4)".to_string(), 649),
+        ]
+    );
+}
+
+#[test]
+fn decanting_sells_leftover_doses_as_smaller_potion() {
+    let dir = cache_dir("decanting_sells_leftover_doses_as_smaller_potion");
+    let report = report(&dir, &["--fixtures", "tests/fixtures/api"]);
+
+    // 300 + 102 + 2,596 doses make 999 potions of 3 doses and one left over
+    assert_eq!(
+        outputs(&report, "Hunter potion(1+2+4) to (3)"),
+        [
+            ("Hunter potion(3)".to_string(), 999),
+            ("Hunter potion(1)".to_string(), 1),
+        ]
+    );
+}
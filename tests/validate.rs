@@ -0,0 +1,48 @@
+//! Offline checks of the recipes against a fixture item mapping.
+
+use std::process::{Command, Output};
+
+fn validate(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_osrs-mm"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("XDG_CONFIG_HOME", "tests/fixtures")
+        .args(args)
+        .args(["validate", "--mapping", "tests/fixtures/api/mapping.json"])
+        .output()
+        .expect("failed to run osrs-mm")
+}
+
+#[test]
+fn builtin_recipes_are_valid() {
+    let output = validate(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("0 errors, 0 warnings"), "{stdout}");
+}
+
+#[test]
+fn invalid_recipes_are_reported() {
+    let output = validate(&[
+        "--no-default-recipes",
+        "--recipes",
+        "tests/fixtures/invalid_recipes.toml",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("error: Clay (clay): item 434 is used as input and output"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#"warning: Clay (clay): item 434 is "Clay", expected "Soft clay""#),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("error: Lobster (cooking): unknown item 99999999"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("error: Attack potion (decanting): item 121 isn't the 2-dose form"),
+        "{stdout}"
+    );
+}